@@ -0,0 +1,900 @@
+//! Worker의 요청/응답 형식과 오류.
+//!
+//! 요청은 JSON 본문(`Request`)으로 보냅니다. 엔드포인트마다 필요한 필드를 더한 요청(`LegalMovesRequest`,
+//! `PlayRequest`)도 `Request`의 필드를 그대로 씁니다. 예전 클라이언트를 위해 헤더(`position`, `Chessembly`,
+//! `Turn` 등)로 보낸 `/bestmove` 요청도 `Request::from_headers`로 같은 형식으로 바꿔 처리하고, 응답도 예전처럼
+//! `[수, 점수]` 배열로 돌려줍니다.
+//!
+//! 컴파일한 스크립트는 isolate마다 소스의 해시로 캐시합니다. `/validate-script`가 돌려준 `script_hash`를
+//! `script` 대신 보내면 스크립트를 다시 보내지 않아도 됩니다. 다른 isolate로 가서 캐시에 없으면
+//! `unknown-script` 오류가 나므로, 그때는 `script`를 다시 보내면 됩니다.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "script": "...",
+//!   "position": "rook:black knight:black ... / ... /rook:white ...",
+//!   "turn": "white",
+//!   "state": { "white": { "castling_oo": false }, "black": { "registers": { "charge": 1 } } },
+//!   "history": ["e2e4", "e7e5"],
+//!   "options": { "depth": 6, "nodes": 5000, "mode": "analyze" }
+//! }
+//! ```
+//!
+//! 실패하면 알맞은 HTTP 상태 코드와 함께 `ApiError`를 JSON으로 돌려줍니다.
+//! `error` 필드로 종류를 구별하고, `message`는 사람이 읽을 설명입니다.
+//!
+//! ```json
+//! { "error": "illegal-move", "ply": 1, "move": "e7e4", "message": "2번째 수 e7e4는 둘 수 없는 수입니다" }
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::chessembly::board::{Board, BoardStatus, MoveParseError};
+use crate::chessembly::cache::{CompiledScript, ScriptCache};
+use crate::chessembly::diagnostics::{diagnose, ScriptDiagnostic};
+use crate::chessembly::{ChessMove, ChessemblyCompiled, Color, MoveGen, Piece, PieceSpan, Position};
+use crate::engine::annotate::AnnotateError;
+use crate::engine::game_logic::GameState;
+
+/// 지금 요청 형식의 버전. 예전 요청을 잘못 해석하게 되는 변경이 있으면 올립니다.
+pub const REQUEST_VERSION: u32 = 1;
+/// isolate마다 캐시해 두는 컴파일된 스크립트의 수
+const SCRIPT_CACHE_CAPACITY: usize = 16;
+
+fn default_version() -> u32 {
+    REQUEST_VERSION
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Request {
+    /// 요청 형식의 버전. 없으면 지금 버전으로 봅니다.
+    #[serde(default = "default_version")]
+    pub version: u32,
+    /// Chessembly 스크립트 전체. 없으면 `script_hash`로 캐시에서 찾습니다.
+    #[serde(default)]
+    pub script: String,
+    /// 전에 보낸 스크립트의 해시 (`/validate-script`의 `script_hash`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script_hash: Option<String>,
+    /// 기물 배치. 위(흑 쪽)부터 8줄을 `/`로 나누고, 줄마다 칸을 공백으로 나눠 `기물:색`으로 적습니다.
+    /// `:`가 없는 칸(예: `.`)은 빈 칸입니다.
+    pub position: String,
+    /// `position`에서 둘 차례
+    pub turn: Color,
+    #[serde(default)]
+    pub state: PositionState,
+    /// `position`부터 이미 둔 수들 (좌표 표기, `ChessMove::notation`).
+    /// 이 수들을 차례로 둔 위치에서 탐색합니다. `annotate` 모드에서는 주석할 수순입니다.
+    #[serde(default)]
+    pub history: Vec<String>,
+    #[serde(default)]
+    pub options: SearchOptions,
+}
+
+/// `/legal-moves` 요청
+#[derive(Clone, Debug, Deserialize)]
+pub struct LegalMovesRequest {
+    #[serde(flatten)]
+    pub game: Request,
+    /// 이 칸 `[x, y]`의 기물의 수만 돌려줍니다.
+    #[serde(default)]
+    pub square: Option<Position>,
+}
+
+/// `/play` 요청: `history`까지 둔 위치에서 수 하나를 둡니다.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PlayRequest {
+    #[serde(flatten)]
+    pub game: Request,
+    /// 둘 수 (좌표 표기)
+    #[serde(rename = "move")]
+    pub mv: String,
+}
+
+/// `/validate-script` 요청
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScriptRequest {
+    pub script: String,
+}
+
+/// 기물 배치만으로는 알 수 없는 상태
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PositionState {
+    #[serde(default)]
+    pub white: SideState,
+    #[serde(default)]
+    pub black: SideState,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SideState {
+    pub castling_oo: bool,
+    pub castling_ooo: bool,
+    /// 이 쪽이 앙파상으로 잡을 수 있는 기물의 칸 `[x, y]`
+    pub enpassant: Vec<Position>,
+    /// 스크립트의 `set-state`/`if-state`가 쓰는 레지스터
+    pub registers: HashMap<String, u8>,
+}
+
+impl Default for SideState {
+    fn default() -> Self {
+        SideState {
+            castling_oo: true,
+            castling_ooo: true,
+            enpassant: Vec::new(),
+            registers: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mode {
+    /// 수 하나를 고릅니다.
+    #[default]
+    Bestmove,
+    /// 모든 합법적인 수의 점수와 분류 (`engine::analysis`)
+    Analyze,
+    /// `history`의 대국 주석과 정확도 (`engine::annotate`)
+    Annotate,
+}
+
+impl FromStr for Mode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bestmove" => Ok(Mode::Bestmove),
+            "analyze" => Ok(Mode::Analyze),
+            "annotate" => Ok(Mode::Annotate),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    #[default]
+    Alphabeta,
+    Mcts,
+    /// 무거운 플레이아웃을 쓰는 MCTS
+    MctsHeavy,
+}
+
+impl FromStr for Backend {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "alphabeta" => Ok(Backend::Alphabeta),
+            "mcts" => Ok(Backend::Mcts),
+            "mcts-heavy" => Ok(Backend::MctsHeavy),
+            _ => Err(()),
+        }
+    }
+}
+
+/// 탐색 옵션. 주지 않은 값은 Worker의 기본값을 씁니다.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub mode: Mode,
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    /// 밀리초. 하드 한도로 쓰고, 절반이 지나면 새 반복을 시작하지 않습니다.
+    pub movetime: Option<f64>,
+    /// 주지 않으면 무작위로 정하고 응답에 넣어 돌려줍니다.
+    pub seed: Option<u64>,
+    pub multi_pv: Option<usize>,
+    pub variety: Option<i32>,
+    pub backend: Backend,
+    /// `false`이면 오프닝 북을 쓰지 않습니다.
+    pub book: Option<bool>,
+    /// 1(가장 약함)부터 10(최대 강도)까지의 난이도. 한도는 다른 옵션보다 느슨해지지 않습니다.
+    /// 잡음과 실수는 알파-베타 탐색에만 있으므로 MCTS 백엔드와 함께 쓸 수 없습니다.
+    pub level: Option<u8>,
+    /// 응답에 탐색 통계를 넣습니다.
+    pub debug: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "error", rename_all = "kebab-case")]
+pub enum ApiError {
+    /// 본문이 JSON 요청이 아니거나, 헤더 값을 읽을 수 없습니다.
+    BadRequest { detail: String },
+    /// 꼭 필요한 필드(헤더로 보낸 요청이면 헤더)가 없습니다.
+    MissingField { field: String },
+    UnsupportedVersion { version: u32, supported: u32 },
+    /// 스크립트에 문제가 있습니다. (`diagnostics::diagnose`)
+    InvalidScript { diagnostics: Vec<ScriptDiagnostic> },
+    InvalidPosition { detail: String },
+    /// 함께 쓸 수 없는 탐색 옵션입니다.
+    UnsupportedOptions { detail: String },
+    /// `history`의 `ply`번째(0부터) 수를 둘 수 없습니다.
+    IllegalMove {
+        ply: usize,
+        #[serde(rename = "move")]
+        mv: String,
+    },
+    /// `history`의 `ply`번째(0부터) 수의 표기에 맞는 수가 여럿입니다. `candidates`의 전체 표기로 보내야 합니다.
+    AmbiguousMove {
+        ply: usize,
+        #[serde(rename = "move")]
+        mv: String,
+        candidates: Vec<String>,
+    },
+    /// `history`의 `ply`번째(0부터) 수 전에, 또는 `history`를 다 둔 뒤(`ply`가 그 길이) 대국이 끝났습니다.
+    GameOver { ply: usize, status: BoardStatus },
+    /// 이 isolate의 캐시에 이 해시의 스크립트가 없습니다. `script`를 다시 보내야 합니다.
+    UnknownScript { script_hash: String },
+    /// 이 id의 게임이 없습니다. (`session::GameStore`)
+    GameNotFound { id: String },
+    /// 수를 두는 사이에 다른 요청이 이 게임에 먼저 두었습니다. 게임을 다시 읽고 보내야 합니다.
+    /// `ply`는 저장된 게임이 지금까지 둔 수입니다.
+    GameConflict { id: String, ply: Option<usize> },
+    /// 탐색하지 못했습니다. (`find_best_move`의 오류 코드)
+    SearchFailed { code: usize },
+    /// 게임 저장소를 읽거나 쓰지 못했습니다.
+    Storage { detail: String },
+    /// 응답을 만들지 못했습니다.
+    Internal { detail: String },
+}
+
+impl ApiError {
+    pub fn status_code(&self) -> u16 {
+        match self {
+            ApiError::BadRequest { .. } | ApiError::MissingField { .. } | ApiError::UnsupportedVersion { .. } => 400,
+            ApiError::InvalidScript { .. }
+            | ApiError::InvalidPosition { .. }
+            | ApiError::UnsupportedOptions { .. }
+            | ApiError::IllegalMove { .. }
+            | ApiError::AmbiguousMove { .. }
+            | ApiError::GameOver { .. } => 422,
+            ApiError::UnknownScript { .. } | ApiError::GameNotFound { .. } => 404,
+            ApiError::GameConflict { .. } => 409,
+            ApiError::SearchFailed { .. } | ApiError::Storage { .. } | ApiError::Internal { .. } => 500,
+        }
+    }
+
+    fn missing(field: &str) -> ApiError {
+        ApiError::MissingField {
+            field: field.to_string(),
+        }
+    }
+
+    fn invalid_position(detail: impl Into<String>) -> ApiError {
+        ApiError::InvalidPosition { detail: detail.into() }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiError::BadRequest { detail } => write!(f, "잘못된 요청입니다: {detail}"),
+            ApiError::MissingField { field } => write!(f, "{field}이(가) 없습니다"),
+            ApiError::UnsupportedVersion { version, supported } => {
+                write!(f, "지원하지 않는 요청 버전입니다: {version} (지원: {supported} 이하)")
+            }
+            ApiError::InvalidScript { diagnostics } => match diagnostics.first() {
+                Some(first) => write!(f, "스크립트에 문제가 {}개 있습니다. {}번째 줄: {}", diagnostics.len(), first.line, first.message),
+                None => write!(f, "스크립트를 컴파일하지 못했습니다"),
+            },
+            ApiError::InvalidPosition { detail } => write!(f, "기물 배치가 올바르지 않습니다: {detail}"),
+            ApiError::UnsupportedOptions { detail } => write!(f, "함께 쓸 수 없는 옵션입니다: {detail}"),
+            ApiError::IllegalMove { ply, mv } => write!(f, "{}번째 수 {mv}는 둘 수 없는 수입니다", ply + 1),
+            ApiError::AmbiguousMove { ply, mv, candidates } => {
+                write!(f, "{}번째 수 {mv}는 어느 수인지 알 수 없습니다: {}", ply + 1, candidates.join(", "))
+            }
+            ApiError::GameOver { status, .. } => match status {
+                BoardStatus::Checkmate => write!(f, "체크메이트로 대국이 끝났습니다"),
+                _ => write!(f, "스테일메이트로 대국이 끝났습니다"),
+            },
+            ApiError::UnknownScript { script_hash } => {
+                write!(f, "캐시에 없는 스크립트입니다: {script_hash}. script를 다시 보내세요")
+            }
+            ApiError::GameNotFound { id } => write!(f, "게임을 찾을 수 없습니다: {id}"),
+            ApiError::GameConflict { id, .. } => {
+                write!(f, "다른 요청이 게임 {id}에 먼저 수를 두었습니다. 게임을 다시 읽고 보내세요")
+            }
+            ApiError::SearchFailed { code } => write!(f, "탐색하지 못했습니다 ({code})"),
+            ApiError::Storage { detail } => write!(f, "게임 저장소를 쓰지 못했습니다: {detail}"),
+            ApiError::Internal { detail } => write!(f, "응답을 만들지 못했습니다: {detail}"),
+        }
+    }
+}
+
+impl From<AnnotateError> for ApiError {
+    fn from(error: AnnotateError) -> Self {
+        match error {
+            AnnotateError::IllegalMove { ply, text } => ApiError::IllegalMove { ply, mv: text },
+            AnnotateError::AmbiguousMove { ply, text, candidates } => ApiError::AmbiguousMove {
+                ply,
+                mv: text,
+                candidates,
+            },
+            AnnotateError::GameOver { ply, status } => ApiError::GameOver { ply, status },
+            AnnotateError::Search { code, .. } => ApiError::SearchFailed { code },
+        }
+    }
+}
+
+/// 오류 응답의 본문
+#[derive(Serialize)]
+pub struct ErrorBody<'e> {
+    #[serde(flatten)]
+    pub error: &'e ApiError,
+    pub message: String,
+}
+
+impl<'e> From<&'e ApiError> for ErrorBody<'e> {
+    fn from(error: &'e ApiError) -> Self {
+        ErrorBody {
+            error,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let body = serde_json::to_vec(&ErrorBody::from(&self)).unwrap_or_default();
+        (status, [(header::CONTENT_TYPE, "application/json")], body).into_response()
+    }
+}
+
+/// JSON 응답. axum의 `json` 기능은 Worker 번들을 키우므로 serde_json으로 직접 만듭니다.
+pub struct Json<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        match serde_json::to_vec(&self.0) {
+            Ok(body) => ([(header::CONTENT_TYPE, "application/json")], body).into_response(),
+            Err(e) => ApiError::Internal { detail: e.to_string() }.into_response(),
+        }
+    }
+}
+
+/// JSON 본문을 읽습니다. 빠진 필드는 `ApiError::MissingField`가 됩니다.
+pub fn parse_body<T: DeserializeOwned>(body: &[u8]) -> Result<T, ApiError> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Err(ApiError::BadRequest {
+            detail: "요청 본문이 비어 있습니다".to_string(),
+        });
+    }
+    serde_json::from_slice(body).map_err(|e| {
+        // serde의 "missing field `script` at line 1 column 2"에서 필드 이름을 꺼냅니다.
+        let message = e.to_string();
+        match message.strip_prefix("missing field `").and_then(|rest| rest.split_once('`')) {
+            Some((field, _)) => ApiError::missing(field),
+            None => ApiError::BadRequest { detail: message },
+        }
+    })
+}
+
+fn header_value<T: FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+impl Request {
+    /// JSON 본문으로 보낸 요청
+    pub fn from_json(body: &[u8]) -> Result<Request, ApiError> {
+        let request: Request = parse_body(body)?;
+        request.check_version()?;
+        Ok(request)
+    }
+
+    pub fn check_version(&self) -> Result<(), ApiError> {
+        if self.version > REQUEST_VERSION {
+            return Err(ApiError::UnsupportedVersion {
+                version: self.version,
+                supported: REQUEST_VERSION,
+            });
+        }
+        Ok(())
+    }
+
+    /// 헤더로 보낸 예전 형식의 요청. `position`, `Chessembly`, `Turn` 헤더가 하나도 없으면 `None`입니다.
+    /// 헤더 값은 ASCII여야 하므로 주석에 한글이 있는 스크립트는 JSON 본문으로 보내야 합니다.
+    pub fn from_headers(headers: &HeaderMap) -> Result<Option<Request>, ApiError> {
+        let header = |name: &str| match headers.get(name) {
+            None => Ok(None),
+            Some(value) => value.to_str().map(Some).map_err(|_| ApiError::BadRequest {
+                detail: format!("{name} 헤더를 읽을 수 없습니다. ASCII가 아닌 값은 JSON 본문으로 보내세요"),
+            }),
+        };
+        let (position, script, turn) = (header("position")?, header("Chessembly")?, header("Turn")?);
+        if position.is_none() && script.is_none() && turn.is_none() {
+            return Ok(None);
+        }
+        let position = position.ok_or(ApiError::missing("position"))?;
+        let script = script.ok_or(ApiError::missing("Chessembly"))?;
+        let turn = match turn.ok_or(ApiError::missing("Turn"))? {
+            "white" => Color::White,
+            "black" => Color::Black,
+            other => return Err(ApiError::invalid_position(format!("Turn은 white나 black이어야 합니다: {other}"))),
+        };
+        let history: String = header_value(headers, "Moves").unwrap_or_default();
+        Ok(Some(Request {
+            version: REQUEST_VERSION,
+            script: script.to_string(),
+            script_hash: None,
+            position: position.to_string(),
+            turn,
+            state: PositionState::default(),
+            history: history.split_whitespace().map(str::to_string).collect(),
+            options: SearchOptions {
+                mode: header_value(headers, "Mode").unwrap_or_default(),
+                depth: header_value(headers, "Depth"),
+                nodes: header_value(headers, "Nodes"),
+                movetime: header_value(headers, "Movetime"),
+                seed: header_value(headers, "Seed"),
+                multi_pv: header_value(headers, "MultiPV"),
+                variety: header_value(headers, "Variety"),
+                backend: header_value(headers, "Backend").unwrap_or_default(),
+                book: header_value::<String>(headers, "Book").map(|book| book != "off"),
+                level: header_value(headers, "Level"),
+                debug: matches!(header_value::<String>(headers, "Debug").as_deref(), Some("1" | "true")),
+            },
+        }))
+    }
+
+    /// 스크립트를 확인하고 컴파일합니다. `script`가 없으면 `script_hash`로 캐시에서 찾습니다.
+    pub fn compile(&self) -> Result<Rc<CompiledScript>, ApiError> {
+        if !self.script.is_empty() {
+            return compile_script(&self.script);
+        }
+        let Some(script_hash) = &self.script_hash else {
+            return Err(ApiError::missing("script"));
+        };
+        u128::from_str_radix(script_hash, 16)
+            .ok()
+            .and_then(|hash| SCRIPTS.with_borrow_mut(|cache| cache.get(hash)))
+            .ok_or_else(|| ApiError::UnknownScript {
+                script_hash: script_hash.clone(),
+            })
+    }
+
+    /// `position`, `turn`, `state`로 만든 보드. `history`는 두지 않습니다.
+    pub fn board<'a>(&'a self, compiled: &'a ChessemblyCompiled<'a>) -> Result<Board<'a>, ApiError> {
+        let mut board = Board::empty(compiled);
+        let mut lines: Vec<&str> = self.position.split('/').collect();
+        // 끝에 `/`를 하나 더 붙여도 됩니다.
+        if lines.len() == 9 && lines[8].trim().is_empty() {
+            lines.pop();
+        }
+        if lines.len() != 8 {
+            return Err(ApiError::invalid_position(format!("8줄이어야 합니다 ({}줄)", lines.len())));
+        }
+        for (y, line) in lines.into_iter().enumerate() {
+            let squares: Vec<&str> = line.split_whitespace().collect();
+            if squares.len() > 8 {
+                return Err(ApiError::invalid_position(format!("{}번째 줄의 칸이 8개보다 많습니다", y + 1)));
+            }
+            for (x, square) in squares.into_iter().enumerate() {
+                let Some((piece_type, color)) = square.split_once(':') else {
+                    continue;
+                };
+                let color = match color {
+                    "white" => Color::White,
+                    "black" => Color::Black,
+                    _ => return Err(ApiError::invalid_position(format!("알 수 없는 색: {square}"))),
+                };
+                if piece_type.is_empty() {
+                    return Err(ApiError::invalid_position(format!("기물 이름이 없습니다: {square}")));
+                }
+                board.board[y][x] = PieceSpan::Piece(Piece { piece_type, color });
+            }
+        }
+        board.turn = self.turn;
+        for (side, state) in [
+            (&mut board.board_state.white, &self.state.white),
+            (&mut board.board_state.black, &self.state.black),
+        ] {
+            side.castling_oo = state.castling_oo;
+            side.castling_ooo = state.castling_ooo;
+            side.enpassant = state.enpassant.clone();
+            side.register = state.registers.iter().map(|(key, value)| (key.as_str(), *value)).collect();
+        }
+        board.update_status();
+        Ok(board)
+    }
+
+    /// `board`에서 `history`까지 둔 보드
+    pub fn current_board<'a>(&'a self, compiled: &'a ChessemblyCompiled<'a>) -> Result<Board<'a>, ApiError> {
+        let mut board = self.board(compiled)?;
+        play_history(&mut board, &self.history)?;
+        Ok(board)
+    }
+}
+
+thread_local! {
+    /// 이 isolate에서 컴파일한 스크립트들
+    static SCRIPTS: RefCell<ScriptCache> = RefCell::new(ScriptCache::new(SCRIPT_CACHE_CAPACITY));
+}
+
+/// 스크립트를 확인하고 컴파일해 캐시에 넣습니다. 이미 캐시에 있으면 그것을 씁니다.
+pub fn compile_script(script: &str) -> Result<Rc<CompiledScript>, ApiError> {
+    SCRIPTS.with_borrow_mut(|cache| {
+        cache.get_or_insert(script, |source| {
+            let diagnostics = diagnose(source);
+            if !diagnostics.is_empty() {
+                return Err(ApiError::InvalidScript { diagnostics });
+            }
+            Ok(CompiledScript::new(source))
+        })
+    })
+}
+
+/// 응답과 요청에 쓰는 스크립트 해시의 형식 (16진수 32자리)
+pub fn format_script_hash(hash: u128) -> String {
+    format!("{hash:032x}")
+}
+
+/// `history`의 수들을 차례로 둡니다.
+pub fn play_history(board: &mut Board, history: &[String]) -> Result<(), ApiError> {
+    for (ply, text) in history.iter().enumerate() {
+        play_move(board, text, ply)?;
+    }
+    Ok(())
+}
+
+/// `ply`번째(0부터) 수 `text`를 둡니다. 대국이 끝났거나 둘 수 없는 수면 오류입니다.
+pub fn play_move<'a>(board: &mut Board<'a>, text: &str, ply: usize) -> Result<ChessMove<'a>, ApiError> {
+    if board.is_terminal() {
+        return Err(ApiError::GameOver {
+            ply,
+            status: board.status(),
+        });
+    }
+    let m = board.parse_move(text).map_err(|e| match e {
+        MoveParseError::Illegal => ApiError::IllegalMove {
+            ply,
+            mv: text.to_string(),
+        },
+        MoveParseError::Ambiguous(candidates) => ApiError::AmbiguousMove {
+            ply,
+            mv: text.to_string(),
+            candidates,
+        },
+    })?;
+    *board = board.make_move_new(&m);
+    Ok(m)
+}
+
+/// 응답에 넣는 수: 좌표 표기와 `ChessMove`의 필드들
+#[derive(Clone, Debug, Serialize)]
+pub struct MoveView<'a> {
+    pub notation: String,
+    #[serde(flatten)]
+    pub mv: ChessMove<'a>,
+}
+
+impl<'a> From<ChessMove<'a>> for MoveView<'a> {
+    fn from(mv: ChessMove<'a>) -> Self {
+        MoveView {
+            notation: mv.notation(),
+            mv,
+        }
+    }
+}
+
+/// 보드를 요청과 같은 형식으로 나타낸 것. 그대로 다음 요청에 쓸 수 있습니다.
+#[derive(Clone, Debug, Serialize)]
+pub struct PositionView {
+    pub position: String,
+    pub turn: Color,
+    pub state: PositionState,
+    pub status: BoardStatus,
+    /// 둘 차례인 쪽이 체크 당했는지
+    pub check: bool,
+}
+
+impl PositionView {
+    pub fn new(board: &mut Board) -> PositionView {
+        let position = board
+            .board
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|square| match square {
+                        PieceSpan::Piece(piece) => {
+                            let color = if piece.color == Color::White { "white" } else { "black" };
+                            format!("{}:{color}", piece.piece_type)
+                        }
+                        PieceSpan::Empty => ".".to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let side = |state: &crate::chessembly::board::BoardState| SideState {
+            castling_oo: state.castling_oo,
+            castling_ooo: state.castling_ooo,
+            enpassant: state.enpassant.clone(),
+            registers: state.register.iter().map(|(key, value)| (key.to_string(), *value)).collect(),
+        };
+        PositionView {
+            position,
+            turn: board.side_to_move(),
+            state: PositionState {
+                white: side(&board.board_state.white),
+                black: side(&board.board_state.black),
+            },
+            status: board.status(),
+            check: board.is_check(),
+        }
+    }
+}
+
+/// 각 쪽이 공격하는 칸 (`MoveGen::get_danger_zones`). 체크 판정과 같이 모든 수의 `take` 칸입니다.
+#[derive(Clone, Debug, Serialize)]
+pub struct AttackedSquares {
+    pub white: Vec<Position>,
+    pub black: Vec<Position>,
+}
+
+impl AttackedSquares {
+    pub fn new(board: &mut Board) -> AttackedSquares {
+        let mut attacked_by = |color| {
+            // `dp`에는 체크를 확인한 킹의 수가 들어 있을 수 있으므로 비우고 다시 만듭니다.
+            board.dp.clear();
+            let mut squares = MoveGen::get_danger_zones(board, color);
+            squares.sort_unstable();
+            squares.dedup();
+            squares
+        };
+        AttackedSquares {
+            white: attacked_by(Color::White),
+            black: attacked_by(Color::Black),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LegalMovesResponse<'a> {
+    pub turn: Color,
+    pub status: BoardStatus,
+    /// 둘 차례인 쪽이 체크 당했는지
+    pub check: bool,
+    /// `false`면 `square`가 상대 기물의 칸이라, `moves`는 체크를 확인하지 않은 수(`ChessemblyCompiled::get_moves`)입니다.
+    pub legal: bool,
+    pub moves: Vec<MoveView<'a>>,
+    pub attacked: AttackedSquares,
+}
+
+impl<'a> LegalMovesResponse<'a> {
+    /// `square`가 없으면 둘 차례인 쪽의 모든 합법적인 수(`MoveGen::new_legal`)를 넣습니다.
+    /// 있으면 그 칸의 기물의 수만 넣습니다. 상대 기물이면 그 기물이 갈 수 있는 칸을 보여 주도록
+    /// 체크를 확인하지 않은 수를 넣고, 빈 칸이면 수가 없습니다.
+    pub fn new(board: &mut Board<'a>, square: Option<Position>) -> Result<LegalMovesResponse<'a>, ApiError> {
+        let turn = board.side_to_move();
+        let (legal, moves) = match square {
+            Some((x, y)) if x as usize >= board.get_width() || y as usize >= board.get_height() => {
+                return Err(ApiError::BadRequest {
+                    detail: format!("square [{x}, {y}]가 보드 밖입니다"),
+                });
+            }
+            Some(square) if board.color_on(&square) == Some(turn.invert()) => {
+                let script = board.script;
+                (false, script.get_moves(board, &square, false))
+            }
+            Some(square) => {
+                let moves = MoveGen::new_legal(board).into_iter().filter(|m| m.from == square).collect();
+                (true, moves)
+            }
+            None => (true, MoveGen::new_legal(board)),
+        };
+        Ok(LegalMovesResponse {
+            turn,
+            status: board.status(),
+            check: board.is_check(),
+            legal,
+            moves: moves.into_iter().map(MoveView::from).collect(),
+            attacked: AttackedSquares::new(board),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PlayResponse<'a> {
+    #[serde(rename = "move")]
+    pub mv: MoveView<'a>,
+    #[serde(flatten)]
+    pub position: PositionView,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ValidateScriptResponse {
+    pub valid: bool,
+    pub diagnostics: Vec<ScriptDiagnostic>,
+    /// 올바른 스크립트면 다음 요청에서 `script` 대신 보낼 수 있는 해시
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script_hash: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HealthResponse {
+    pub status: &'static str,
+    /// 이 Worker의 패키지 버전
+    pub version: &'static str,
+    /// 지원하는 가장 높은 요청 형식 버전
+    pub request_version: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAMELEON: &str = include_str!("../chameleon.csm");
+    const START: &str = "rook:black knight:black bishop:black queen:black king:black bishop:black knight:black rook:black/\
+        pawn:black pawn:black pawn:black pawn:black pawn:black pawn:black pawn:black pawn:black/./././.\
+        /pawn:white pawn:white pawn:white pawn:white pawn:white pawn:white pawn:white pawn:white\
+        /rook:white knight:white bishop:white queen:white king:white bishop:white knight:white rook:white";
+
+    fn request_json(extra: &str) -> String {
+        format!(r#"{{"script": {}, "position": "{START}", "turn": "white"{extra}}}"#, serde_json::to_string(CHAMELEON).unwrap())
+    }
+
+    fn error_json(error: &ApiError) -> serde_json::Value {
+        serde_json::to_value(ErrorBody::from(error)).unwrap()
+    }
+
+    #[test]
+    fn minimal_request_takes_defaults() {
+        let request = Request::from_json(request_json("").as_bytes()).unwrap();
+        assert_eq!(request.version, REQUEST_VERSION);
+        assert_eq!(request.script_hash, None);
+        assert!(request.history.is_empty());
+        assert!(request.state.white.castling_oo && request.state.black.castling_ooo);
+        assert_eq!(request.options.mode, Mode::Bestmove);
+        assert_eq!(request.options.backend, Backend::Alphabeta);
+        assert_eq!(request.options.depth, None);
+
+        let request = Request::from_json(
+            request_json(r#", "history": ["e2e4"], "options": {"mode": "analyze", "backend": "mcts-heavy", "depth": 4}"#)
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(request.history, ["e2e4"]);
+        assert_eq!(request.options.mode, Mode::Analyze);
+        assert_eq!(request.options.backend, Backend::MctsHeavy);
+        assert_eq!(request.options.depth, Some(4));
+    }
+
+    #[test]
+    fn malformed_bodies_are_bad_requests() {
+        let error = Request::from_json(b"  ").unwrap_err();
+        assert!(matches!(error, ApiError::BadRequest { .. }));
+        assert_eq!(error.status_code(), 400);
+        assert!(matches!(Request::from_json(b"{\"script\": 1"), Err(ApiError::BadRequest { .. })));
+
+        let error = Request::from_json(br#"{"script": "", "turn": "white"}"#).unwrap_err();
+        assert_eq!(error_json(&error)["error"], "missing-field");
+        assert_eq!(error_json(&error)["field"], "position");
+        assert_eq!(error.status_code(), 400);
+
+        let error = Request::from_json(request_json(r#", "version": 2"#).as_bytes()).unwrap_err();
+        assert!(matches!(error, ApiError::UnsupportedVersion { version: 2, supported: REQUEST_VERSION }));
+        assert_eq!(error.status_code(), 400);
+    }
+
+    #[test]
+    fn legacy_headers_become_a_request() {
+        assert!(Request::from_headers(&HeaderMap::new()).unwrap().is_none());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("position", START.parse().unwrap());
+        let error = Request::from_headers(&headers).unwrap_err();
+        assert!(matches!(&error, ApiError::MissingField { field } if field == "Chessembly"));
+
+        headers.insert("Chessembly", "piece(rook) take-move(1, 0) repeat(1);".parse().unwrap());
+        headers.insert("Turn", "black".parse().unwrap());
+        headers.insert("Moves", "e2e4 e7e5".parse().unwrap());
+        headers.insert("Depth", "3".parse().unwrap());
+        headers.insert("Book", "off".parse().unwrap());
+        headers.insert("Mode", "annotate".parse().unwrap());
+        let request = Request::from_headers(&headers).unwrap().unwrap();
+        assert_eq!(request.turn, Color::Black);
+        assert_eq!(request.history, ["e2e4", "e7e5"]);
+        assert_eq!(request.options.depth, Some(3));
+        assert_eq!(request.options.book, Some(false));
+        assert_eq!(request.options.mode, Mode::Annotate);
+
+        headers.insert("Turn", "red".parse().unwrap());
+        assert_eq!(Request::from_headers(&headers).unwrap_err().status_code(), 422);
+    }
+
+    #[test]
+    fn scripts_and_positions_are_checked() {
+        let error = compile_script("piece(rook) fly(1, 0);").err().unwrap();
+        assert_eq!(error_json(&error)["error"], "invalid-script");
+        assert_eq!(error.status_code(), 422);
+
+        let mut request = Request::from_json(request_json("").as_bytes()).unwrap();
+        request.script.clear();
+        assert!(matches!(request.compile(), Err(ApiError::MissingField { field }) if field == "script"));
+
+        let request = Request::from_json(request_json("").as_bytes()).unwrap();
+        let script = request.compile().unwrap();
+        let mut bad = request.clone();
+        bad.position = "king:white/king:black".to_string();
+        let error = bad.board(script.compiled()).unwrap_err();
+        assert!(matches!(error, ApiError::InvalidPosition { .. }));
+        assert_eq!(error.status_code(), 422);
+        bad.position = START.replace("king:black", "king:red");
+        assert!(matches!(bad.board(script.compiled()), Err(ApiError::InvalidPosition { .. })));
+    }
+
+    #[test]
+    fn history_errors_name_the_ply() {
+        let mut request = Request::from_json(request_json(r#", "history": ["e2e4", "e7e4"]"#).as_bytes()).unwrap();
+        let script = request.compile().unwrap();
+        let error = request.current_board(script.compiled()).unwrap_err();
+        assert_eq!(error.status_code(), 422);
+        let body = error_json(&error);
+        assert_eq!(body["error"], "illegal-move");
+        assert_eq!(body["ply"], 1);
+        assert_eq!(body["move"], "e7e4");
+        assert_eq!(body["message"], "2번째 수 e7e4는 둘 수 없는 수입니다");
+
+        // 바보 메이트 뒤에 둔 수
+        request.history = ["f2f3", "e7e5", "g2g4", "d8h4", "e2e4"].map(str::to_string).to_vec();
+        let error = request.current_board(script.compiled()).unwrap_err();
+        assert!(matches!(error, ApiError::GameOver { ply: 4, status: BoardStatus::Checkmate }));
+        assert_eq!(error_json(&error)["status"], "checkmate");
+    }
+
+    #[test]
+    fn cached_scripts_are_found_by_hash() {
+        let script = compile_script(CHAMELEON).unwrap();
+        let hash = format_script_hash(script.hash());
+        assert_eq!(hash.len(), 32);
+
+        let mut request = Request::from_json(request_json("").as_bytes()).unwrap();
+        request.script.clear();
+        request.script_hash = Some(hash);
+        assert!(Rc::ptr_eq(&request.compile().unwrap(), &script));
+
+        request.script_hash = Some(format_script_hash(script.hash() ^ 1));
+        let error = request.compile().err().unwrap();
+        assert!(matches!(error, ApiError::UnknownScript { .. }));
+        assert_eq!(error.status_code(), 404);
+    }
+
+    #[test]
+    fn error_codes() {
+        let cases = [
+            (ApiError::UnknownScript { script_hash: "00".to_string() }, "unknown-script", 404),
+            (ApiError::UnsupportedOptions { detail: String::new() }, "unsupported-options", 422),
+            (ApiError::GameNotFound { id: "x".to_string() }, "game-not-found", 404),
+            (ApiError::GameConflict { id: "x".to_string(), ply: Some(3) }, "game-conflict", 409),
+            (ApiError::SearchFailed { code: 1 }, "search-failed", 500),
+            (ApiError::Storage { detail: String::new() }, "storage", 500),
+            (ApiError::Internal { detail: String::new() }, "internal", 500),
+        ];
+        for (error, name, status) in cases {
+            assert_eq!(error_json(&error)["error"], name);
+            assert_eq!(error.status_code(), status);
+            let response = error.into_response();
+            assert_eq!(response.status().as_u16(), status);
+            assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! 대국 기록이나 자기 대국으로 오프닝 북을 만듭니다.
+//!
+//! 기록 파일은 한 줄에 한 대국이며, 좌표 표기(`e2e4`, `e7e8=queen`)의 수를 공백으로 나눠 적고
+//! 끝에 결과(`1-0`, `0-1`, `1/2-1/2`, `*`)를 붙일 수 있습니다. `#`으로 시작하는 줄은 무시합니다.
+//! 기록 파일을 주지 않으면 `engine::search`끼리 자기 대국해서 만듭니다.
+//! 결과는 `OpeningBook::from_bytes`로 읽는 바이너리 파일이며, Worker 번들에 넣어 쓸 수 있습니다.
+//!
+//! ```text
+//! cargo run --release --bin book -- <script.csm> [--records games.txt] [--games 64] [--depth 4]
+//!     [--nodes 2000] [--plies 16] [--min-weight 2] [--seed 1] [--out book.bin]
+//! ```
+
+use std::process::ExitCode;
+
+use chessembly_bot::chessembly::board::{Board, BoardStatus};
+use chessembly_bot::chessembly::{ChessMove, ChessemblyCompiled, Color};
+use chessembly_bot::engine::book::BookBuilder;
+use chessembly_bot::engine::game_logic::GameState;
+use chessembly_bot::engine::rng::Rng;
+use chessembly_bot::engine::search::{find_best_move, SearchConfig, SearchLimits};
+
+/// 자기 대국에서 최선의 수와 이 점수(센티폰) 이내인 수 중에서 골라 여러 갈래를 만듭니다.
+const ROOT_VARIETY: i32 = 20;
+/// 이 수를 넘으면 무승부로 봅니다.
+const MAX_PLIES: usize = 160;
+
+struct Options {
+    script: String,
+    records: Option<String>,
+    games: u64,
+    depth: u8,
+    nodes: u64,
+    plies: usize,
+    min_weight: u32,
+    seed: u64,
+    out: String,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut options = Options {
+        script: String::new(),
+        records: None,
+        games: 64,
+        depth: 4,
+        nodes: 2000,
+        plies: 16,
+        min_weight: 2,
+        seed: 1,
+        out: "book.bin".to_string(),
+    };
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name}에 값이 없습니다"));
+        match arg.as_str() {
+            "--records" => options.records = Some(value(&arg)?),
+            "--games" => options.games = value(&arg)?.parse().map_err(|_| "--games는 정수입니다")?,
+            "--depth" => options.depth = value(&arg)?.parse().map_err(|_| "--depth는 정수입니다")?,
+            "--nodes" => options.nodes = value(&arg)?.parse().map_err(|_| "--nodes는 정수입니다")?,
+            "--plies" => options.plies = value(&arg)?.parse().map_err(|_| "--plies는 정수입니다")?,
+            "--min-weight" => options.min_weight = value(&arg)?.parse().map_err(|_| "--min-weight는 정수입니다")?,
+            "--seed" => options.seed = value(&arg)?.parse().map_err(|_| "--seed는 정수입니다")?,
+            "--out" => options.out = value(&arg)?,
+            _ if arg.starts_with("--") => return Err(format!("알 수 없는 옵션: {arg}")),
+            _ => options.script = arg,
+        }
+    }
+    if options.script.is_empty() {
+        return Err("사용법: book <script.csm> [--records FILE] [--games N] [--depth N] [--nodes N] [--plies N] [--min-weight N] [--seed N] [--out FILE]".to_string());
+    }
+    Ok(options)
+}
+
+/// 백의 관점 결과(1, 0.5, 0)
+fn white_result(board: &Board) -> f64 {
+    match board.status() {
+        BoardStatus::Checkmate if board.side_to_move() == Color::White => 0.0,
+        BoardStatus::Checkmate => 1.0,
+        _ => 0.5,
+    }
+}
+
+/// 기록 파일의 대국들을 더합니다. 읽은 대국 수를 반환합니다.
+fn add_records(builder: &mut BookBuilder, compiled: &ChessemblyCompiled, records: &str) -> Result<usize, String> {
+    let mut games = 0;
+    for (number, line) in records.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let start = Board::new(compiled);
+        let mut board = start.clone();
+        let mut moves = Vec::new();
+        let mut result = None;
+        for token in line.split_whitespace() {
+            match token {
+                "1-0" => result = Some(1.0),
+                "0-1" => result = Some(0.0),
+                "1/2-1/2" => result = Some(0.5),
+                "*" => {}
+                _ => {
+                    let m = board
+                        .parse_move(token)
+                        .map_err(|e| format!("{}번째 줄: {token}: {e}", number + 1))?;
+                    board = board.make_move(&m);
+                    moves.push(m);
+                }
+            }
+        }
+        // 결과를 적지 않았어도 메이트로 끝난 대국이면 결과를 알 수 있습니다.
+        if result.is_none() && board.is_terminal() {
+            result = Some(white_result(&board));
+        }
+        builder.add_game(&start, &moves, result);
+        games += 1;
+    }
+    Ok(games)
+}
+
+/// 자기 대국 한 판을 두고 수순과 백의 관점 결과를 반환합니다.
+fn self_play<'a>(compiled: &'a ChessemblyCompiled<'a>, options: &Options, game: u64) -> (Vec<ChessMove<'a>>, f64) {
+    let mut rng = Rng::new(options.seed.wrapping_mul(0x9e37_79b9).wrapping_add(game));
+    let mut board = Board::new(compiled);
+    let mut moves = Vec::new();
+    for _ in 0..MAX_PLIES {
+        if board.is_terminal() {
+            break;
+        }
+        // 북에 넣을 앞부분만 넓게 고르고, 그 뒤로는 최선의 수로 결과를 냅니다.
+        let config = SearchConfig {
+            limits: SearchLimits {
+                max_nodes: Some(options.nodes),
+                ..SearchLimits::depth(options.depth)
+            },
+            seed: rng.next_u64(),
+            root_variety: (moves.len() < options.plies).then_some(ROOT_VARIETY),
+            ..SearchConfig::default()
+        };
+        let Ok(result) = find_best_move(&mut board, &config) else {
+            break;
+        };
+        board = board.make_move(&result.best_move);
+        moves.push(result.best_move);
+    }
+    (moves, white_result(&board))
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let script = std::fs::read_to_string(&options.script).map_err(|e| format!("{}: {e}", options.script))?;
+    let compiled = ChessemblyCompiled::from_script(&script).map_err(|_| "스크립트를 컴파일하지 못했습니다".to_string())?;
+    let mut builder = BookBuilder::new(compiled.script_hash(), options.plies);
+
+    match &options.records {
+        Some(path) => {
+            let records = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+            let games = add_records(&mut builder, &compiled, &records).map_err(|e| format!("{path}: {e}"))?;
+            println!("대국 {games}개를 읽었습니다");
+        }
+        None => {
+            for game in 0..options.games {
+                let (moves, result) = self_play(&compiled, options, game);
+                println!("대국 {}/{}: 결과 {result}, {}수", game + 1, options.games, moves.len());
+                builder.add_game(&Board::new(&compiled), &moves, Some(result));
+            }
+        }
+    }
+
+    let book = builder.build(options.min_weight);
+    if book.is_empty() {
+        return Err("북에 넣을 수가 없습니다. --min-weight를 낮춰 보세요".to_string());
+    }
+    std::fs::write(&options.out, book.to_bytes()).map_err(|e| format!("{}: {e}", options.out))?;
+    println!("수 {}개를 {}에 저장했습니다", book.len(), options.out);
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let result = parse_options().and_then(|options| run(&options));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! 난이도마다 기준 난이도와 대국시켜 Elo 차이를 잽니다.
+//!
+//! 각 난이도가 기준 난이도와 색을 바꿔 가며 대국하고, 얻은 점수 비율을 Elo 차이로 바꿉니다.
+//! `engine::skill::SKILL_LEVELS`의 Elo는 어림값이므로, 이 결과로 확인하거나 고칠 수 있습니다.
+//!
+//! ```text
+//! cargo run --release --bin skill -- <script.csm> [--games 20] [--baseline 10] [--nodes 2000] [--seed 1]
+//! ```
+
+use std::process::ExitCode;
+
+use chessembly_bot::chessembly::board::{Board, BoardStatus};
+use chessembly_bot::chessembly::{ChessemblyCompiled, Color};
+use chessembly_bot::engine::game_logic::GameState;
+use chessembly_bot::engine::rng::Rng;
+use chessembly_bot::engine::search::{find_best_move, SearchConfig, SearchLimits};
+use chessembly_bot::engine::skill::{skill_level, SKILL_LEVELS};
+
+/// 대국마다 처음 이만큼은 무작위로 두어 서로 다른 대국이 나오게 합니다.
+const RANDOM_PLIES: usize = 4;
+/// 이 수를 넘으면 무승부로 봅니다.
+const MAX_PLIES: usize = 160;
+/// 기본 탐색 깊이 (최고 난이도에서 쓰는 한도)
+const MAX_DEPTH: u8 = 6;
+
+struct Options {
+    script: String,
+    games: u64,
+    baseline: u8,
+    nodes: u64,
+    seed: u64,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut options = Options {
+        script: String::new(),
+        games: 20,
+        baseline: SKILL_LEVELS.len() as u8,
+        nodes: 2000,
+        seed: 1,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name}에 값이 없습니다"));
+        match arg.as_str() {
+            "--games" => options.games = value(&arg)?.parse().map_err(|_| "--games는 정수입니다")?,
+            "--baseline" => options.baseline = value(&arg)?.parse().map_err(|_| "--baseline은 정수입니다")?,
+            "--nodes" => options.nodes = value(&arg)?.parse().map_err(|_| "--nodes는 정수입니다")?,
+            "--seed" => options.seed = value(&arg)?.parse().map_err(|_| "--seed는 정수입니다")?,
+            _ if arg.starts_with("--") => return Err(format!("알 수 없는 옵션: {arg}")),
+            _ => options.script = arg,
+        }
+    }
+    if options.script.is_empty() {
+        return Err("사용법: skill <script.csm> [--games N] [--baseline N] [--nodes N] [--seed N]".to_string());
+    }
+    if skill_level(options.baseline).is_none() {
+        return Err(format!("--baseline은 1부터 {}까지입니다", SKILL_LEVELS.len()));
+    }
+    Ok(options)
+}
+
+/// `white`와 `black` 난이도로 한 판을 두고 백의 관점 결과(1, 0.5, 0)를 반환합니다.
+fn play(compiled: &ChessemblyCompiled, white: u8, black: u8, options: &Options, game: u64) -> f64 {
+    let mut rng = Rng::new(options.seed.wrapping_mul(0x9e37_79b9).wrapping_add(game));
+    let mut board = Board::new(compiled);
+
+    for ply in 0..MAX_PLIES {
+        if board.is_terminal() {
+            break;
+        }
+        let chosen = if ply < RANDOM_PLIES {
+            let moves = board.get_legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            moves[rng.below(moves.len())].clone()
+        } else {
+            let level = if board.side_to_move() == Color::White { white } else { black };
+            let mut config = SearchConfig {
+                limits: SearchLimits {
+                    max_nodes: Some(options.nodes),
+                    ..SearchLimits::depth(MAX_DEPTH)
+                },
+                seed: rng.next_u64(),
+                ..SearchConfig::default()
+            };
+            skill_level(level).unwrap().apply(&mut config);
+            let Ok(result) = find_best_move(&mut board, &config) else {
+                break;
+            };
+            result.best_move
+        };
+        board = board.make_move(&chosen);
+    }
+
+    match board.status() {
+        BoardStatus::Checkmate if board.side_to_move() == Color::White => 0.0,
+        BoardStatus::Checkmate => 1.0,
+        _ => 0.5,
+    }
+}
+
+/// 점수 비율을 Elo 차이로 바꿉니다. 전승이나 전패는 ±800에서 자릅니다.
+fn elo_difference(score: f64) -> f64 {
+    let score = score.clamp(0.01, 0.99);
+    (-400.0 * (1.0 / score - 1.0).log10()).clamp(-800.0, 800.0)
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let script = std::fs::read_to_string(&options.script).map_err(|e| format!("{}: {e}", options.script))?;
+    let compiled = ChessemblyCompiled::from_script(&script).map_err(|_| "스크립트를 컴파일하지 못했습니다".to_string())?;
+
+    println!("단계  점수   Elo 차이");
+    for level in 1..=SKILL_LEVELS.len() as u8 {
+        if level == options.baseline {
+            continue;
+        }
+        let mut total = 0.0;
+        for game in 0..options.games {
+            // 짝수 판은 이 난이도가 백, 홀수 판은 흑입니다. 같은 시작 수순을 두 번씩 둡니다.
+            let score = if game % 2 == 0 {
+                play(&compiled, level, options.baseline, options, game / 2)
+            } else {
+                1.0 - play(&compiled, options.baseline, level, options, game / 2)
+            };
+            total += score;
+        }
+        let score = total / options.games.max(1) as f64;
+        println!("{level:>4}  {score:.3}  {:+.0}", elo_difference(score));
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let result = parse_options().and_then(|options| run(&options));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! 작은 기물 구성의 엔드게임 테이블을 만듭니다.
+//!
+//! 기물 구성은 `백 기물들/흑 기물들`입니다. (예: `king,chameleon/king`)
+//! 잡기나 변신으로 넘어가는 기물 구성의 테이블도 함께 만들고, 각 테이블의 요약을 출력합니다.
+//! 결과는 `Tablebases::from_bytes`로 읽는 바이너리 파일이며, Worker 번들에 넣어 쓸 수 있습니다.
+//!
+//! ```text
+//! cargo run --release --bin tablebase -- <script.csm> <기물 구성>... [--out tables.bin]
+//! ```
+
+use std::process::ExitCode;
+
+use chessembly_bot::chessembly::ChessemblyCompiled;
+use chessembly_bot::engine::tablebase::{TableSummary, Tablebases};
+
+struct Options {
+    script: String,
+    materials: Vec<String>,
+    out: String,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut options = Options {
+        script: String::new(),
+        materials: Vec::new(),
+        out: "tables.bin".to_string(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => options.out = args.next().ok_or("--out에 값이 없습니다")?,
+            _ if arg.starts_with("--") => return Err(format!("알 수 없는 옵션: {arg}")),
+            _ if options.script.is_empty() => options.script = arg,
+            _ => options.materials.push(arg),
+        }
+    }
+    if options.script.is_empty() || options.materials.is_empty() {
+        return Err("사용법: tablebase <script.csm> <기물 구성>... [--out FILE]".to_string());
+    }
+    Ok(options)
+}
+
+fn describe(side: &str, summary: &TableSummary) -> String {
+    let total = summary.wins + summary.draws + summary.losses + summary.unknown;
+    let verdict = if total > 0 && summary.wins == total {
+        "모든 위치에서 이깁니다"
+    } else if summary.wins > 0 {
+        "이기는 위치가 있습니다"
+    } else {
+        "이기는 위치가 없습니다"
+    };
+    format!(
+        "  {side} 차례: 승 {} / 무 {} / 패 {} / 모름 {}, 가장 긴 메이트 {}플라이 — {verdict}",
+        summary.wins, summary.draws, summary.losses, summary.unknown, summary.longest_mate
+    )
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let script = std::fs::read_to_string(&options.script).map_err(|e| format!("{}: {e}", options.script))?;
+    let compiled = ChessemblyCompiled::from_script(&script).map_err(|_| "스크립트를 컴파일하지 못했습니다".to_string())?;
+
+    let mut tablebases = Tablebases::new(compiled.script_hash());
+    for material in &options.materials {
+        tablebases.generate(&compiled, material).map_err(|e| e.to_string())?;
+    }
+
+    let mut tables: Vec<_> = tablebases.tables().collect();
+    tables.sort_by(|a, b| a.material.cmp(&b.material));
+    for table in tables {
+        let [white, black] = table.summary();
+        println!("{}", table.material);
+        println!("{}", describe("백", &white));
+        println!("{}", describe("흑", &black));
+    }
+
+    std::fs::write(&options.out, tablebases.to_bytes()).map_err(|e| format!("{}: {e}", options.out))?;
+    println!("{}에 저장했습니다", options.out);
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let result = parse_options().and_then(|options| run(&options));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,370 @@
+//! 자기 대국으로 평가 가중치를 맞추는 튜너 (Texel 방식).
+//!
+//! 주어진 스크립트로 `engine::search`끼리 대국해 조용한 위치와 대국 결과를 모으고,
+//! 평가 점수를 승률로 바꾼 값과 실제 결과의 오차가 가장 작아지도록 가중치를 고칩니다.
+//! 결과는 `PositionalEvaluator::from_json`으로 읽을 수 있는 JSON 가중치 파일입니다.
+//! Worker에서 쓰려면 저장할 때 알려 주는 스크립트 해시와 함께 `src/lib.rs`의 `EVALUATORS`에 넣습니다.
+//!
+//! ```text
+//! cargo run --release --bin tune -- <script.csm> [--games 32] [--depth 2] [--nodes 400]
+//!     [--iterations 300] [--seed 1] [--init weights.json] [--out weights.json]
+//! ```
+
+use std::collections::HashMap;
+use std::process::ExitCode;
+
+use chessembly_bot::chessembly::board::{Board, BoardStatus};
+use chessembly_bot::chessembly::{ChessemblyCompiled, Color};
+use chessembly_bot::engine::eval::{EvalTrace, EvalWeights, Evaluated, PositionalEvaluator, PARAM_COUNT, TOTAL_PHASE};
+use chessembly_bot::engine::game_logic::GameState;
+use chessembly_bot::engine::rng::Rng;
+use chessembly_bot::engine::search::{find_best_move, SearchConfig, SearchLimits};
+use chessembly_bot::engine::values::script_piece_value;
+
+/// 대국마다 처음 이만큼은 무작위로 두어 서로 다른 대국이 나오게 합니다.
+const RANDOM_PLIES: usize = 6;
+/// 이 수를 넘으면 무승부로 봅니다.
+const MAX_PLIES: usize = 160;
+/// 최선의 수와 이 점수(센티폰) 이내인 수 중에서 골라 대국을 다양하게 합니다.
+const ROOT_VARIETY: i32 = 15;
+/// 이보다 크게 기운 위치는 결과가 뻔하므로 학습에 쓰지 않습니다.
+const MAX_SAMPLE_SCORE: i32 = 1500;
+/// Adam 학습률 (센티폰)
+const LEARNING_RATE: f64 = 1.0;
+
+struct Options {
+    script: String,
+    games: u64,
+    depth: u8,
+    nodes: u64,
+    iterations: usize,
+    seed: u64,
+    init: Option<String>,
+    out: String,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut options = Options {
+        script: String::new(),
+        games: 32,
+        depth: 2,
+        nodes: 400,
+        iterations: 300,
+        seed: 1,
+        init: None,
+        out: "weights.json".to_string(),
+    };
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name}에 값이 없습니다"));
+        match arg.as_str() {
+            "--games" => options.games = value(&arg)?.parse().map_err(|_| "--games는 정수입니다")?,
+            "--depth" => options.depth = value(&arg)?.parse().map_err(|_| "--depth는 정수입니다")?,
+            "--nodes" => options.nodes = value(&arg)?.parse().map_err(|_| "--nodes는 정수입니다")?,
+            "--iterations" => options.iterations = value(&arg)?.parse().map_err(|_| "--iterations는 정수입니다")?,
+            "--seed" => options.seed = value(&arg)?.parse().map_err(|_| "--seed는 정수입니다")?,
+            "--init" => options.init = Some(value(&arg)?),
+            "--out" => options.out = value(&arg)?,
+            _ if arg.starts_with("--") => return Err(format!("알 수 없는 옵션: {arg}")),
+            _ => options.script = arg,
+        }
+    }
+    if options.script.is_empty() {
+        return Err("사용법: tune <script.csm> [--games N] [--depth N] [--nodes N] [--iterations N] [--seed N] [--init FILE] [--out FILE]".to_string());
+    }
+    Ok(options)
+}
+
+/// 한 판을 두고, 학습에 쓸 위치들과 백의 관점 결과(1, 0.5, 0)를 반환합니다.
+fn self_play(compiled: &ChessemblyCompiled, evaluator: &PositionalEvaluator, options: &Options, game: u64) -> (Vec<EvalTrace>, f64) {
+    let mut rng = Rng::new(options.seed.wrapping_mul(0x9e37_79b9).wrapping_add(game));
+    let mut board = Board::new(compiled);
+    let mut traces = Vec::new();
+
+    for ply in 0..MAX_PLIES {
+        if board.is_terminal() {
+            break;
+        }
+        let chosen = if ply < RANDOM_PLIES {
+            let moves = board.get_legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            moves[rng.below(moves.len())].clone()
+        } else {
+            let config = SearchConfig {
+                limits: SearchLimits {
+                    max_nodes: Some(options.nodes),
+                    ..SearchLimits::depth(options.depth)
+                },
+                seed: rng.next_u64(),
+                root_variety: Some(ROOT_VARIETY),
+                ..SearchConfig::default()
+            };
+            let mut state = Evaluated { state: board.clone(), evaluator };
+            let Ok(result) = find_best_move(&mut state, &config) else {
+                break;
+            };
+            // 잡기가 오가는 중이거나 체크인 위치는 정적 평가가 틀리기 쉬우므로 빼고 모읍니다.
+            if result.score.abs() < MAX_SAMPLE_SCORE && !state.is_check() && board.noisy_gain(&result.best_move).is_none() {
+                traces.push(evaluator.trace(&mut board.clone()));
+            }
+            result.best_move
+        };
+        board = board.make_move(&chosen);
+    }
+
+    let result = match board.status() {
+        BoardStatus::Checkmate if board.side_to_move() == Color::White => 0.0,
+        BoardStatus::Checkmate => 1.0,
+        _ => 0.5,
+    };
+    (traces, result)
+}
+
+/// 한 위치의 평가를 (가중치 번호, 계수)의 일차식으로 펼친 것. 기물 가치는 `PARAM_COUNT` 뒤에 붙습니다.
+struct Sample {
+    coefficients: Vec<(usize, f64)>,
+    result: f64,
+}
+
+fn linearize(trace: &EvalTrace, result: f64, materials: &mut Vec<String>) -> Sample {
+    let phase = trace.phase as f64 / TOTAL_PHASE as f64;
+    let mut coefficients: Vec<(usize, f64)> = trace
+        .terms
+        .iter()
+        .map(|&(param, mg, eg)| (param, mg as f64 * phase + eg as f64 * (1.0 - phase)))
+        .collect();
+    for (piece, count) in &trace.material {
+        let index = match materials.iter().position(|name| name == piece) {
+            Some(index) => index,
+            None => {
+                materials.push(piece.clone());
+                materials.len() - 1
+            }
+        };
+        coefficients.push((PARAM_COUNT + index, *count as f64));
+    }
+    Sample { coefficients, result }
+}
+
+/// `linearize`한 일차식에 쓸 가중치와, 맞추지 않고 그대로 둘 가중치 표시.
+/// 기물 가치는 `weights`에 없으면 스크립트의 값을 씁니다.
+fn linear_weights(compiled: &ChessemblyCompiled, weights: &EvalWeights, materials: &[String]) -> (Vec<f64>, Vec<bool>) {
+    let mut linear: Vec<f64> = weights.params().iter().map(|&w| w as f64).collect();
+    let mut frozen = vec![false; linear.len()];
+    for piece in materials {
+        let value = weights
+            .piece_values
+            .get(piece)
+            .copied()
+            .unwrap_or_else(|| script_piece_value(compiled, piece));
+        linear.push(value as f64);
+        // 킹은 양쪽에서 상쇄되므로 맞출 수 없습니다.
+        frozen.push(piece == "king");
+    }
+    (linear, frozen)
+}
+
+/// `linear_weights`의 반대: 맞춘 일차식의 가중치를 반올림해 `base`에 넣습니다.
+fn eval_weights(base: &EvalWeights, linear: &[f64], materials: &[String]) -> EvalWeights {
+    let mut weights = base.clone();
+    let rounded: Vec<i32> = linear.iter().map(|w| w.round() as i32).collect();
+    weights.set_params(&rounded[..PARAM_COUNT]);
+    for (piece, value) in materials.iter().zip(&rounded[PARAM_COUNT..]) {
+        if piece != "king" {
+            weights.piece_values.insert(piece.clone(), *value);
+        }
+    }
+    weights
+}
+
+fn predict(sample: &Sample, weights: &[f64]) -> f64 {
+    sample.coefficients.iter().map(|&(index, c)| weights[index] * c).sum()
+}
+
+fn sigmoid(k: f64, score: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score / 400.0))
+}
+
+fn mean_error(samples: &[Sample], weights: &[f64], k: f64) -> f64 {
+    let total: f64 = samples
+        .iter()
+        .map(|sample| (sample.result - sigmoid(k, predict(sample, weights))).powi(2))
+        .sum();
+    total / samples.len() as f64
+}
+
+/// 현재 가중치에서 오차가 가장 작은 척도 K를 찾습니다.
+fn fit_scale(samples: &[Sample], weights: &[f64]) -> f64 {
+    (1..=300)
+        .map(|i| i as f64 * 0.01)
+        .min_by(|a, b| mean_error(samples, weights, *a).total_cmp(&mean_error(samples, weights, *b)))
+        .unwrap_or(1.0)
+}
+
+/// Adam으로 오차를 줄입니다. `frozen`인 가중치는 고치지 않습니다.
+fn optimize(samples: &[Sample], weights: &mut [f64], frozen: &[bool], k: f64, iterations: usize) {
+    let (beta1, beta2, epsilon) = (0.9, 0.999, 1e-8);
+    let mut m = vec![0.0; weights.len()];
+    let mut v = vec![0.0; weights.len()];
+    let scale = k * std::f64::consts::LN_10 / 400.0;
+
+    for t in 1..=iterations {
+        let mut gradient = vec![0.0; weights.len()];
+        for sample in samples {
+            let s = sigmoid(k, predict(sample, weights));
+            let g = 2.0 * (s - sample.result) * s * (1.0 - s) * scale;
+            for &(index, c) in &sample.coefficients {
+                gradient[index] += g * c;
+            }
+        }
+
+        for i in 0..weights.len() {
+            if frozen[i] {
+                continue;
+            }
+            let g = gradient[i] / samples.len() as f64;
+            m[i] = beta1 * m[i] + (1.0 - beta1) * g;
+            v[i] = beta2 * v[i] + (1.0 - beta2) * g * g;
+            let m_hat = m[i] / (1.0 - beta1.powi(t as i32));
+            let v_hat = v[i] / (1.0 - beta2.powi(t as i32));
+            weights[i] -= LEARNING_RATE * m_hat / (v_hat.sqrt() + epsilon);
+        }
+
+        if t % 50 == 0 || t == iterations {
+            println!("반복 {t}: 오차 {:.6}", mean_error(samples, weights, k));
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let script = std::fs::read_to_string(&options.script).map_err(|e| format!("{}: {e}", options.script))?;
+    let compiled = ChessemblyCompiled::from_script(&script).map_err(|_| "스크립트를 컴파일하지 못했습니다".to_string())?;
+
+    let evaluator = match &options.init {
+        Some(path) => {
+            let json = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+            PositionalEvaluator::from_json(&json).map_err(|e| format!("{path}: {e}"))?
+        }
+        None => PositionalEvaluator::default(),
+    };
+
+    // 1. 자기 대국으로 학습 데이터를 모읍니다.
+    let mut materials = Vec::new();
+    let mut samples = Vec::new();
+    let mut outcomes: HashMap<&str, usize> = HashMap::new();
+    for game in 0..options.games {
+        let (traces, result) = self_play(&compiled, &evaluator, options, game);
+        *outcomes
+            .entry(match result {
+                1.0 => "백 승",
+                0.0 => "흑 승",
+                _ => "무승부",
+            })
+            .or_default() += 1;
+        println!("대국 {}/{}: 결과 {result}, 위치 {}개", game + 1, options.games, traces.len());
+        samples.extend(traces.iter().map(|trace| linearize(trace, result, &mut materials)));
+    }
+    println!("{outcomes:?}, 위치 {}개", samples.len());
+    if samples.is_empty() {
+        return Err("학습에 쓸 위치가 없습니다".to_string());
+    }
+
+    // 2. 일차식의 가중치: 평가 가중치 다음에 기물 가치가 옵니다.
+    let (mut weights, frozen) = linear_weights(&compiled, &evaluator.weights, &materials);
+
+    // 3. 척도를 맞춘 뒤 오차를 줄입니다.
+    let k = fit_scale(&samples, &weights);
+    println!("K = {k:.2}, 처음 오차 {:.6}", mean_error(&samples, &weights, k));
+    optimize(&samples, &mut weights, &frozen, k, options.iterations);
+
+    // 4. 가중치 파일로 씁니다.
+    let tuned = eval_weights(&evaluator.weights, &weights, &materials);
+    let json = serde_json::to_string_pretty(&tuned).map_err(|e| e.to_string())?;
+    std::fs::write(&options.out, json).map_err(|e| format!("{}: {e}", options.out))?;
+    println!("{}에 저장했습니다 (스크립트 해시 {:#018x})", options.out, compiled.script_hash());
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let result = parse_options().and_then(|options| run(&options));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chessembly_bot::engine::eval::Evaluator;
+
+    const CHAMELEON: &str = include_str!("../../chameleon.csm");
+
+    #[test]
+    fn linearization_matches_the_evaluator_and_round_trips() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let evaluator = PositionalEvaluator::default();
+        let mut board = Board::new(&compiled);
+        let mut rng = Rng::new(5);
+        let mut traces = Vec::new();
+        for _ in 0..30 {
+            traces.push((evaluator.trace(&mut board.clone()), board.clone()));
+            let moves = board.get_legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            board = board.make_move(&moves[rng.below(moves.len())]);
+        }
+
+        let mut materials = Vec::new();
+        let samples: Vec<Sample> = traces.iter().map(|(trace, _)| linearize(trace, 0.5, &mut materials)).collect();
+        let (linear, frozen) = linear_weights(&compiled, &evaluator.weights, &materials);
+        assert_eq!(linear.len(), PARAM_COUNT + materials.len());
+        assert_eq!(frozen.iter().filter(|&&f| f).count(), 1);
+
+        // 일차식의 값은 평가 함수의 백 기준 점수와 같습니다. (보간의 정수 나눗셈만큼 다를 수 있음)
+        for (sample, (_, board)) in samples.iter().zip(&traces) {
+            let white = match board.side_to_move() {
+                Color::White => 1,
+                Color::Black => -1,
+            };
+            let score = evaluator.evaluate(&mut board.clone()) * white;
+            assert!((predict(sample, &linear) - score as f64).abs() <= 1.0, "{} vs {score}", predict(sample, &linear));
+        }
+
+        // 가중치 파일로 되돌리면 같은 가중치와 스크립트의 기물 가치가 나옵니다.
+        let weights = eval_weights(&evaluator.weights, &linear, &materials);
+        assert_eq!(weights.params(), evaluator.weights.params());
+        for piece in materials.iter().filter(|piece| *piece != "king") {
+            assert_eq!(weights.piece_values[piece], script_piece_value(&compiled, piece));
+        }
+        assert_eq!(linear_weights(&compiled, &weights, &materials).0, linear);
+    }
+
+    #[test]
+    fn optimization_lowers_the_error_on_synthetic_samples() {
+        // 0번 특징이 양수면 백이 이기고 음수면 지는 위치들. 처음 가중치 0에서는 모두 반반으로 예측합니다.
+        let samples: Vec<Sample> = (1..=20)
+            .flat_map(|i| {
+                let x = i as f64;
+                [
+                    Sample { coefficients: vec![(0, x), (1, 1.0)], result: 1.0 },
+                    Sample { coefficients: vec![(0, -x), (1, 1.0)], result: 0.0 },
+                ]
+            })
+            .collect();
+        let mut weights = vec![0.0, 0.0];
+        let before = mean_error(&samples, &weights, 1.0);
+        optimize(&samples, &mut weights, &[false, true], 1.0, 100);
+        let after = mean_error(&samples, &weights, 1.0);
+        assert!(after < before, "{after} vs {before}");
+        assert!(weights[0] > 0.0);
+        // 고정한 가중치는 그대로입니다.
+        assert_eq!(weights[1], 0.0);
+    }
+}
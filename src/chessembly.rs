@@ -2,6 +2,8 @@ use std::cmp::Ordering;
 use std::{collections::HashMap, hash::Hash};
 mod behavior;
 pub mod board;
+pub mod cache;
+pub mod diagnostics;
 pub mod moves;
 use behavior::{Behavior, BehaviorChain};
 pub(crate) use board::Board;
@@ -17,7 +19,8 @@ pub enum GameResult {
     DrawDeclared,
 }
 
-#[derive(PartialOrd, PartialEq, Eq, Copy, Clone, Debug, Hash)]
+#[derive(PartialOrd, PartialEq, Eq, Copy, Clone, Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Color {
     White,
     Black,
@@ -44,7 +47,7 @@ pub enum PieceSpan<'a> {
     Empty,
 }
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Debug, Hash, Serialize)]
 pub enum MoveType {
@@ -61,6 +64,10 @@ pub enum MoveType {
 pub type Position = (u8, u8);
 pub type DeltaPosition = (i8, i8);
 
+fn square_name((x, y): Position) -> String {
+    format!("{}{}", (b'a' + x) as char, 8 - y)
+}
+
 #[derive(Clone, Eq, PartialOrd, PartialEq, Debug, Hash, Serialize)]
 pub struct ChessMove<'a> {
     pub from: Position,
@@ -88,6 +95,35 @@ impl<'a> ChessMove<'a> {
     pub fn get_promotion(&self) -> &Option<&'a str> {
         &self.transition
     }
+
+    /// 좌표 표기. 파일은 a-h, 랭크는 백의 첫 줄(y = 7)이 1입니다.
+    /// 잡는 칸이 도착 칸과 다르면(앙파상, `catch`) `x잡는칸`을, 변신하는 수에는 `=기물`을 붙입니다.
+    /// (예: `e2e4`, `e5d6xd5`, `d4d4xf6=mirrored-pawn`, `e7e8=queen`)
+    ///
+    /// 수 생성은 한 기물에서 (도착 칸, 잡는 칸)마다 수를 하나만 남기므로(`push_node`),
+    /// 합법적인 수들 사이에서 이 표기는 하나뿐입니다. `move_type`과 `state_change`는 수를 가르지 않습니다.
+    pub fn notation(&self) -> String {
+        let mut text = square_name(self.from) + &square_name(self.move_to);
+        if self.take != self.move_to {
+            text.push('x');
+            text.push_str(&square_name(self.take));
+        }
+        if let Some(piece) = self.transition {
+            text.push('=');
+            text.push_str(piece);
+        }
+        text
+    }
+
+    /// 잡는 칸을 뺀 예전 표기. `Board::parse_move`가 헷갈리지 않을 때만 받아 줍니다.
+    pub(crate) fn short_notation(&self) -> String {
+        let mut text = square_name(self.from) + &square_name(self.move_to);
+        if let Some(piece) = self.transition {
+            text.push('=');
+            text.push_str(piece);
+        }
+        text
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -167,40 +203,68 @@ impl<'a> ChessemblyCompiled<'a> {
         }
     }
 
+    /// 주석과 공백을 뺀, 컴파일된 스크립트의 해시. 오프닝 북처럼 변형마다 따로 쓰는 데이터의 열쇠입니다.
+    /// 명령마다 `Behavior::canonical`로 적고, 명령은 공백, 체인은 `;`로 이은 글의 해시입니다.
+    pub fn script_hash(&self) -> u64 {
+        let canonical = self
+            .chains
+            .iter()
+            .map(|chain| chain.iter().map(Behavior::canonical).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>()
+            .join(";");
+        board::fnv1a(&canonical)
+    }
+
+    /// 스크립트의 `value(기물, n)` 지시문으로 정한 기물 가치 (센티폰)
+    pub fn value_override(&self, piece: &str) -> Option<i32> {
+        self.chains.iter().flatten().find_map(|behavior| match behavior {
+            Behavior::Value((name, value)) if *name == piece => Some(*value),
+            _ => None,
+        })
+    }
+
     pub fn from_script(script: &'a str) -> Result<ChessemblyCompiled<'a>, ()> {
         let mut ret = ChessemblyCompiled::new();
-        let chains = script.split(';');
-        for chain_str in chains {
-            if chain_str.trim().starts_with('#') {
-                continue;
-            } else if chain_str.chars().all(char::is_whitespace) {
-                continue;
-            } else {
-                ret.add_command();
-                let mut i = 0;
-                let mut j = 0;
-                while j < chain_str.len() - 1 {
-                    if chain_str[j..j + 1].chars().all(char::is_whitespace) {
-                        if chain_str[j + 1..j + 2]
-                            .chars()
-                            .all(|c| char::is_alphabetic(c) || c == '{' || c == '}')
-                        {
-                            if chain_str[i..j].trim().len() > 0 {
-                                ret.push_behavior(Behavior::from_str(&chain_str[i..j].trim()));
-                                i = j;
-                            }
-                        }
-                    }
-                    j += 1;
-                }
-                if !chain_str[i..].chars().all(char::is_whitespace) {
-                    ret.push_behavior(Behavior::from_str(&chain_str[i..].trim()));
-                }
+        for chain_str in ChessemblyCompiled::chains_of(script) {
+            ret.add_command();
+            for fragment in ChessemblyCompiled::fragments_of(chain_str) {
+                ret.push_behavior(Behavior::from_str(fragment));
             }
         }
         Ok(ret)
     }
 
+    /// 주석(`#`으로 시작)과 빈 체인을 뺀 체인들
+    pub(crate) fn chains_of(script: &str) -> impl Iterator<Item = &str> {
+        script
+            .split(';')
+            .filter(|chain_str| !chain_str.trim().starts_with('#') && !chain_str.chars().all(char::is_whitespace))
+    }
+
+    /// 체인을 명령 조각으로 나눕니다. 공백 뒤에 글자나 `{`, `}`가 오면 새 조각입니다.
+    /// 바이트 단위로 자르므로 체인에 ASCII가 아닌 문자가 있으면 안 됩니다. (`diagnostics::diagnose` 참고)
+    pub(crate) fn fragments_of(chain_str: &str) -> Vec<&str> {
+        let mut fragments = Vec::new();
+        let mut i = 0;
+        let mut j = 0;
+        while j < chain_str.len() - 1 {
+            if chain_str[j..j + 1].chars().all(char::is_whitespace)
+                && chain_str[j + 1..j + 2]
+                    .chars()
+                    .all(|c| char::is_alphabetic(c) || c == '{' || c == '}')
+                && !chain_str[i..j].trim().is_empty()
+            {
+                fragments.push(chain_str[i..j].trim());
+                i = j;
+            }
+            j += 1;
+        }
+        if !chain_str[i..].chars().all(char::is_whitespace) {
+            fragments.push(chain_str[i..].trim());
+        }
+        fragments
+    }
+
     fn wall_collision(anchor: &Position, delta: &DeltaPosition, board: &Board, color: Color) -> WallCollision {
         let a0 = (anchor.0 as i8) + delta.0;
         let a1 = (anchor.1 as i8) - delta.1;
@@ -1193,3 +1257,32 @@ impl<'a> ChessemblyCompiled<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAMELEON: &str = include_str!("../chameleon.csm");
+
+    #[test]
+    fn canonical_commands_parse_back_to_the_same_behavior() {
+        let others = "value(knight, 3.25); piece(pawn) if-state(moved, 0) set-state(moved, 1) transition \
+            label(1) peek(0, 1) jne(1) not check while do end { corner-top-left(-1, 1) };";
+        for script in [CHAMELEON, others] {
+            let compiled = ChessemblyCompiled::from_script(script).unwrap();
+            for behavior in compiled.chains.iter().flatten() {
+                let text = behavior.canonical();
+                assert_eq!(&Behavior::from_str(&text), behavior, "{text}");
+            }
+        }
+    }
+
+    #[test]
+    fn script_hash_ignores_comments_and_spacing_but_not_commands() {
+        let a = ChessemblyCompiled::from_script("piece(rook) take-move(1, 0) repeat(1);\n# 주석;\n").unwrap();
+        let b = ChessemblyCompiled::from_script("  piece(rook)   take-move(1,0)\n repeat(1) ;").unwrap();
+        let c = ChessemblyCompiled::from_script("piece(rook) take-move(0, 1) repeat(1);").unwrap();
+        assert_eq!(a.script_hash(), b.script_hash());
+        assert_ne!(a.script_hash(), c.script_hash());
+    }
+}
@@ -39,6 +39,9 @@ pub enum Behavior<'a> {
     IfState((&'a str, u8)),
     Transition(&'a str),
     Piece(String),
+    /// value(기물, n): 엔진이 추정한 기물 가치 대신 n폰으로 봅니다. (센티폰으로 저장)
+    /// 수를 만들지 않으므로 혼자 체인이어야 합니다. 인터프리터는 이 명령에서 체인을 멈춥니다.
+    Value((&'a str, i32)),
 }
 
 pub type BehaviorChain<'a> = Vec<Behavior<'a>>;
@@ -119,6 +122,14 @@ impl<'a> Behavior<'a> {
                     .map(|s| s.parse::<u8>().unwrap_or(0))
                     .unwrap_or(0),
             ));
+        } else if cmd == "value" {
+            return Behavior::Value((
+                params_vec.first().unwrap_or(&""),
+                params_vec
+                    .get(1)
+                    .map(|s| (s.parse::<f64>().unwrap_or(0.0) * 100.0).round() as i32)
+                    .unwrap_or(0),
+            ));
         } else if cmd == "if-state" {
             return Behavior::IfState((
                 params_vec.get(0).unwrap_or(&""),
@@ -377,6 +388,53 @@ impl<'a> Behavior<'a> {
         Behavior::End
     }
 
+    /// 스크립트 문법으로 다시 적은 명령. `ChessemblyCompiled::script_hash`가 씁니다.
+    /// 저장된 오프닝 북과 엔드게임 테이블의 열쇠가 되므로, 적는 방식을 바꾸면 그 파일들의 버전도 올려야 합니다.
+    pub fn canonical(&self) -> String {
+        let delta = |name: &str, (x, y): &DeltaPosition| format!("{name}({x},{y})");
+        match self {
+            Behavior::TakeMove(d) => delta("take-move", d),
+            Behavior::Take(d) => delta("take", d),
+            Behavior::Repeat(n) => format!("repeat({n})"),
+            Behavior::Move(d) => delta("move", d),
+            Behavior::Catch(d) => delta("catch", d),
+            Behavior::Peek(d) => delta("peek", d),
+            Behavior::Observe(d) => delta("observe", d),
+            Behavior::While => "while".to_string(),
+            Behavior::Jump(d) => delta("jump", d),
+            Behavior::Do => "do".to_string(),
+            Behavior::Bound(d) => delta("bound", d),
+            Behavior::Edge(d) => delta("edge", d),
+            Behavior::EdgeTop(d) => delta("edge-top", d),
+            Behavior::EdgeLeft(d) => delta("edge-left", d),
+            Behavior::EdgeRight(d) => delta("edge-right", d),
+            Behavior::EdgeBottom(d) => delta("edge-bottom", d),
+            Behavior::Corner(d) => delta("corner", d),
+            Behavior::CornerTopLeft(d) => delta("corner-top-left", d),
+            Behavior::CornerTopRight(d) => delta("corner-top-right", d),
+            Behavior::CornerBottomLeft(d) => delta("corner-bottom-left", d),
+            Behavior::CornerBottomRight(d) => delta("corner-bottom-right", d),
+            Behavior::Not => "not".to_string(),
+            Behavior::Jmp(n) => format!("jmp({n})"),
+            Behavior::Jne(n) => format!("jne({n})"),
+            Behavior::BlockOpen => "{".to_string(),
+            Behavior::BlockClose => "}".to_string(),
+            Behavior::Label(n) => format!("label({n})"),
+            Behavior::End => "end".to_string(),
+            Behavior::Danger(d) => delta("danger", d),
+            Behavior::Check => "check".to_string(),
+            Behavior::Enemy(d) => delta("enemy", d),
+            Behavior::Friendly(d) => delta("friendly", d),
+            Behavior::PieceOn((piece, (x, y))) => format!("piece-on({piece},{x},{y})"),
+            Behavior::SetState((key, value)) => format!("set-state({key},{value})"),
+            Behavior::IfState((key, value)) => format!("if-state({key},{value})"),
+            Behavior::Transition("") => "transition".to_string(),
+            Behavior::Transition(piece) => format!("transition({piece})"),
+            Behavior::Piece(piece) => format!("piece({piece})"),
+            Behavior::Value((piece, value)) => format!("value({piece},{})", *value as f64 / 100.0),
+        }
+    }
+
     fn reflect_turn_vector(position: &DeltaPosition, turn: Color) -> DeltaPosition {
         if turn == Color::Black {
             return (-position.0, -position.1);
@@ -1,6 +1,13 @@
+use std::cell::{OnceCell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+use serde::Serialize;
+
 use super::{ChessMove, ChessemblyCompiled, Color, HashMap, MoveGen, Piece, PieceSpan, Position};
 
-#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Eq)]
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum BoardStatus {
     Ongoing,
     Stalemate,
@@ -15,12 +22,59 @@ pub struct BoardState<'a> {
     pub register: HashMap<&'a str, u8>,
 }
 
+/// `Board::parse_move`의 오류
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MoveParseError {
+    /// 이 위치에서 둘 수 없는 수
+    Illegal,
+    /// 이 표기에 맞는 합법적인 수가 여럿입니다. 각 후보의 전체 표기
+    Ambiguous(Vec<String>),
+}
+
+impl fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveParseError::Illegal => write!(f, "둘 수 없는 수입니다"),
+            MoveParseError::Ambiguous(candidates) => write!(f, "어느 수인지 알 수 없습니다: {}", candidates.join(", ")),
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct BothBoardState<'a> {
     pub black: BoardState<'a>,
     pub white: BoardState<'a>,
 }
 
+/// 기물 이름별 가치(센티폰). 스크립트마다 하나를 두고 나눠 씁니다.
+pub type PieceValues = Rc<RefCell<HashMap<String, i32>>>;
+
+/// 엔진이 스크립트마다 구해 두는 기물 가치 표. (`engine::values` 참고)
+/// 처음 물어볼 때 스크립트의 표를 찾아 두고, 이 보드에서 이어지는 보드들이 함께 씁니다.
+/// 스크립트로 정해지는 값이므로 보드를 비교할 때는 보지 않습니다.
+#[derive(Clone, Default)]
+pub struct PieceValueCache(OnceCell<PieceValues>);
+
+impl PieceValueCache {
+    pub fn get_or_init(&self, init: impl FnOnce() -> PieceValues) -> &RefCell<HashMap<String, i32>> {
+        self.0.get_or_init(init)
+    }
+}
+
+impl PartialEq for PieceValueCache {
+    fn eq(&self, _: &PieceValueCache) -> bool {
+        true
+    }
+}
+
+impl Eq for PieceValueCache {}
+
+impl fmt::Debug for PieceValueCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("PieceValueCache")
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Board<'a> {
     pub board: [[PieceSpan<'a>; 8]; 8],
@@ -29,6 +83,7 @@ pub struct Board<'a> {
     pub script: &'a ChessemblyCompiled<'a>,
     pub status: BoardStatus,
     pub dp: HashMap<Position, Vec<ChessMove<'a>>>,
+    pub piece_values: PieceValueCache,
 }
 
 impl<'a> Board<'a> {
@@ -62,6 +117,7 @@ impl<'a> Board<'a> {
             script: script,
             turn: Color::White,
             status: BoardStatus::Ongoing,
+            piece_values: PieceValueCache::default(),
         };
         for i in 0..8 {
             for j in 0..8 {
@@ -126,9 +182,29 @@ impl<'a> Board<'a> {
             script,
             turn: Color::White,
             status: BoardStatus::Ongoing,
+            piece_values: PieceValueCache::default(),
         }
     }
 
+    /// 캐슬링 없이 주어진 기물만 놓은 테스트용 위치. 칸은 (x, y)이고 y = 0이 8번째 줄입니다.
+    #[cfg(test)]
+    pub(crate) fn from_pieces(
+        script: &'a ChessemblyCompiled,
+        turn: Color,
+        pieces: &[((usize, usize), &'a str, Color)],
+    ) -> Board<'a> {
+        let mut board = Board::empty(script);
+        for state in [&mut board.board_state.white, &mut board.board_state.black] {
+            state.castling_oo = false;
+            state.castling_ooo = false;
+        }
+        for &((x, y), piece_type, color) in pieces {
+            board.board[y][x] = PieceSpan::Piece(Piece { piece_type, color });
+        }
+        board.turn = turn;
+        board
+    }
+
     pub fn new(script: &'a ChessemblyCompiled) -> Board<'a> {
         Board {
             dp: HashMap::new(),
@@ -195,6 +271,7 @@ impl<'a> Board<'a> {
             script,
             turn: Color::White,
             status: BoardStatus::Ongoing,
+            piece_values: PieceValueCache::default(),
         }
     }
 
@@ -283,16 +360,36 @@ impl<'a> Board<'a> {
         }
 
         ret.turn = ret.turn.invert();
+        ret.update_status();
+        ret
+    }
 
-        let turn = ret.side_to_move();
-        if MoveGen::get_all_moves(&mut ret, turn, true).len() == 0 {
-            if self.script.is_check(&mut ret, turn.invert()) {
-                ret.status = BoardStatus::Checkmate;
+    /// 둘 차례인 쪽의 합법적인 수가 없으면 체크메이트나 스테일메이트로 표시합니다.
+    /// 수를 두지 않고 직접 만든 보드는 이것을 불러야 끝난 대국인지 알 수 있습니다.
+    pub fn update_status(&mut self) {
+        let turn = self.side_to_move();
+        if MoveGen::get_all_moves(self, turn, true).is_empty() {
+            if self.script.is_check(self, turn.invert()) {
+                self.status = BoardStatus::Checkmate;
             } else {
-                ret.status = BoardStatus::Stalemate;
+                self.status = BoardStatus::Stalemate;
             }
         }
-        ret
+    }
+
+    /// 좌표 표기(`ChessMove::notation`)로 적은 합법적인 수를 찾습니다.
+    /// 잡는 칸을 뺀 예전 표기도 받지만, 그 표기에 맞는 수가 여럿이면 `MoveParseError::Ambiguous`입니다.
+    pub fn parse_move(&mut self, text: &str) -> Result<ChessMove<'a>, MoveParseError> {
+        let legal = MoveGen::new_legal(self);
+        let mut found: Vec<&ChessMove<'a>> = legal.iter().filter(|m| m.notation() == text).collect();
+        if found.is_empty() {
+            found = legal.iter().filter(|m| m.short_notation() == text).collect();
+        }
+        match found.as_slice() {
+            [] => Err(MoveParseError::Illegal),
+            [m] => Ok((*m).clone()),
+            _ => Err(MoveParseError::Ambiguous(found.iter().map(|m| m.notation()).collect())),
+        }
     }
 
     #[inline]
@@ -300,6 +397,38 @@ impl<'a> Board<'a> {
         self.make_move_new_nc(node, true)
     }
 
+    /// 기물 배치, 차례, 캐슬링/앙파상 상태와 레지스터로 만든 64비트 해시.
+    /// 기물 종류가 스크립트마다 다르므로 Zobrist 키를 미리 만들지 않고
+    /// (칸, 색, 기물 이름)에서 키를 바로 계산해 XOR합니다.
+    pub fn position_hash(&self) -> u64 {
+        let mut hash = 0;
+        for j in 0..8u8 {
+            for i in 0..8u8 {
+                if let PieceSpan::Piece(piece) = &self.board[j as usize][i as usize] {
+                    let square = (j as u64) << 3 | i as u64;
+                    let color = if piece.color == Color::White { 0 } else { 1 << 6 };
+                    hash ^= mix64(fnv1a(piece.piece_type) ^ square ^ color);
+                }
+            }
+        }
+        if self.turn == Color::Black {
+            hash ^= mix64(0x5349_4445);
+        }
+        for (salt, state) in [(1u64, &self.board_state.white), (2u64, &self.board_state.black)] {
+            let flags = state.castling_oo as u64 | (state.castling_ooo as u64) << 1;
+            hash ^= mix64(salt << 56 ^ flags);
+            for (x, y) in &state.enpassant {
+                hash ^= mix64(salt << 56 ^ 1 << 48 ^ (*x as u64) << 8 ^ *y as u64);
+            }
+            for (key, value) in &state.register {
+                if *value != 0 {
+                    hash ^= mix64(salt << 56 ^ 2 << 48 ^ fnv1a(key) ^ *value as u64);
+                }
+            }
+        }
+        hash
+    }
+
     #[inline]
     pub const fn status(&self) -> BoardStatus {
         self.status
@@ -344,3 +473,103 @@ impl<'a> Board<'a> {
         8
     }
 }
+
+pub(crate) fn fnv1a(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// splitmix64의 마무리 함수. 비슷한 입력도 고르게 흩어 줍니다.
+fn mix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAMELEON: &str = include_str!("../../chameleon.csm");
+
+    fn place<'a>(board: &mut Board<'a>, piece_type: &'a str, color: Color, (x, y): Position) {
+        board.board[y as usize][x as usize] = PieceSpan::Piece(Piece { piece_type, color });
+    }
+
+    /// 카멜레온에서 두 칸 대각선에 폰이 넷 있는 위치. 잡는 칸만 다른 `catch` 수가 넷 나옵니다.
+    fn chameleon_catches<'a>(compiled: &'a ChessemblyCompiled<'a>) -> Board<'a> {
+        let mut board = Board::empty(compiled);
+        // 백 킹은 흑 폰들이 공격하지 않는 칸에 둡니다.
+        place(&mut board, "king", Color::White, (7, 0));
+        place(&mut board, "king", Color::Black, (0, 4));
+        place(&mut board, "chameleon", Color::White, (3, 4));
+        for square in [(5, 2), (5, 6), (1, 2), (1, 6)] {
+            place(&mut board, "pawn", Color::Black, square);
+        }
+        board
+    }
+
+    fn assert_round_trip(board: &mut Board) {
+        for m in MoveGen::new_legal(board) {
+            assert_eq!(board.parse_move(&m.notation()), Ok(m.clone()), "{}", m.notation());
+        }
+    }
+
+    #[test]
+    fn catches_with_the_same_destination_have_distinct_notation() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let mut board = chameleon_catches(&compiled);
+        let mut catches: Vec<String> = MoveGen::new_legal(&mut board)
+            .iter()
+            .filter(|m| m.transition == Some("mirrored-pawn"))
+            .map(|m| m.notation())
+            .collect();
+        catches.sort();
+        assert_eq!(
+            catches,
+            ["d4d4xb2=mirrored-pawn", "d4d4xb6=mirrored-pawn", "d4d4xf2=mirrored-pawn", "d4d4xf6=mirrored-pawn"]
+        );
+        assert_round_trip(&mut board);
+    }
+
+    #[test]
+    fn short_notation_is_accepted_only_when_unambiguous() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let mut board = chameleon_catches(&compiled);
+        match board.parse_move("d4d4=mirrored-pawn") {
+            Err(MoveParseError::Ambiguous(candidates)) => assert_eq!(candidates.len(), 4),
+            other => panic!("{other:?}"),
+        }
+        assert_eq!(board.parse_move("d4e5").map(|m| m.move_to), Ok((4, 3)));
+        assert_eq!(board.parse_move("d4d8"), Err(MoveParseError::Illegal));
+    }
+
+    #[test]
+    fn every_legal_move_round_trips_through_notation() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let mut board = Board::new(&compiled);
+        // 비숍 자리에 카멜레온을 두어 `catch`와 변신도 나오게 합니다.
+        for (x, y, color) in [(2, 7, Color::White), (5, 7, Color::White), (2, 0, Color::Black), (5, 0, Color::Black)] {
+            place(&mut board, "chameleon", color, (x, y));
+        }
+        // 모든 위치를 다 보면 느리므로 네 수마다, 그리고 변신하는 수가 있는 위치만 봅니다.
+        let mut transitions = 0;
+        for ply in 0..40 {
+            let moves = MoveGen::new_legal(&mut board);
+            if moves.is_empty() {
+                break;
+            }
+            let transforming = moves.iter().filter(|m| m.transition.is_some()).count();
+            if ply % 4 == 0 || transforming > 0 {
+                assert_round_trip(&mut board);
+                transitions += transforming;
+            }
+            board = board.make_move_new(&moves[(ply * 7 + 3) % moves.len()]);
+        }
+        assert!(transitions > 0);
+    }
+}
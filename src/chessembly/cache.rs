@@ -0,0 +1,168 @@
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use self_cell::self_cell;
+use sha2::{Digest, Sha256};
+
+use super::ChessemblyCompiled;
+
+/// 스크립트 소스의 해시. 캐시의 열쇠이고, 클라이언트가 스크립트 대신 보낼 수 있는 값입니다.
+/// 주석까지 포함한 소스 전체의 해시이므로 `ChessemblyCompiled::script_hash`와는 다릅니다.
+///
+/// 해시만으로 캐시의 스크립트를 쓰므로, 다른 사람의 스크립트와 해시가 같은 스크립트를 일부러 만들 수 없도록
+/// SHA-256의 앞 128비트를 씁니다.
+pub fn source_hash(source: &str) -> u128 {
+    let digest = Sha256::digest(source.as_bytes());
+    u128::from_be_bytes(digest[..16].try_into().unwrap())
+}
+
+type Compiled<'a> = ChessemblyCompiled<'a>;
+
+self_cell! {
+    /// 소스와 그 소스를 빌리는 컴파일 결과. 소스는 바뀌지 않고, 컴파일 결과가 먼저 버려집니다.
+    struct SourceAndCompiled {
+        owner: Box<str>,
+        #[covariant]
+        dependent: Compiled,
+    }
+}
+
+/// 소스를 직접 가지고 있는 컴파일된 스크립트. 요청의 문자열에 묶이지 않으므로 요청이 끝나도 둘 수 있습니다.
+pub struct CompiledScript {
+    cell: SourceAndCompiled,
+    hash: u128,
+}
+
+impl CompiledScript {
+    pub fn new(source: &str) -> CompiledScript {
+        CompiledScript {
+            cell: SourceAndCompiled::new(Box::from(source), |source| {
+                ChessemblyCompiled::from_script(source).unwrap_or_else(|_| ChessemblyCompiled::new())
+            }),
+            hash: source_hash(source),
+        }
+    }
+
+    pub fn compiled(&self) -> &ChessemblyCompiled<'_> {
+        self.cell.borrow_dependent()
+    }
+
+    pub fn source(&self) -> &str {
+        self.cell.borrow_owner()
+    }
+
+    pub fn hash(&self) -> u128 {
+        self.hash
+    }
+}
+
+/// 최근에 쓴 것부터 `capacity`개까지 두는 컴파일된 스크립트 캐시 (LRU).
+/// 스크립트 수가 적으므로 목록을 차례로 찾습니다.
+pub struct ScriptCache {
+    capacity: usize,
+    /// 앞쪽일수록 최근에 쓴 것
+    entries: VecDeque<Rc<CompiledScript>>,
+}
+
+impl ScriptCache {
+    pub fn new(capacity: usize) -> ScriptCache {
+        ScriptCache {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// 해시로 찾습니다. 찾으면 가장 최근에 쓴 것으로 옮깁니다.
+    pub fn get(&mut self, hash: u128) -> Option<Rc<CompiledScript>> {
+        let i = self.entries.iter().position(|script| script.hash == hash)?;
+        let script = self.entries.remove(i)?;
+        self.entries.push_front(script.clone());
+        Some(script)
+    }
+
+    /// 같은 소스가 캐시에 있으면 그것을, 없으면 `compile`로 만들어 넣은 것을 돌려줍니다.
+    /// 해시가 같아도 소스가 다르면 새로 컴파일해 바꿔 넣습니다.
+    pub fn get_or_insert<E>(
+        &mut self,
+        source: &str,
+        compile: impl FnOnce(&str) -> Result<CompiledScript, E>,
+    ) -> Result<Rc<CompiledScript>, E> {
+        if let Some(script) = self.get(source_hash(source)) {
+            if script.source() == source {
+                return Ok(script);
+            }
+            self.entries.pop_front();
+        }
+        let script = Rc::new(compile(source)?);
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_back();
+        }
+        self.entries.push_front(script.clone());
+        Ok(script)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(n: usize) -> String {
+        format!("piece(rook) take-move(1, 0) repeat({n});")
+    }
+
+    fn compile(source: &str) -> Result<CompiledScript, ()> {
+        Ok(CompiledScript::new(source))
+    }
+
+    #[test]
+    fn hashes_are_sha256_prefixes() {
+        // SHA-256("abc")의 앞 16바이트
+        assert_eq!(source_hash("abc"), 0xba78_16bf_8f01_cfea_4141_40de_5dae_2223);
+        assert_ne!(source_hash(&script(1)), source_hash(&script(2)));
+        assert_eq!(CompiledScript::new(&script(1)).hash(), source_hash(&script(1)));
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used() {
+        let mut cache = ScriptCache::new(2);
+        let one = cache.get_or_insert(&script(1), compile).unwrap();
+        cache.get_or_insert(&script(2), compile).unwrap();
+        // 1을 쓰면 2가 가장 오래된 것이 됩니다.
+        assert!(cache.get(one.hash()).is_some());
+        cache.get_or_insert(&script(3), compile).unwrap();
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.get(source_hash(&script(2))).is_none());
+        assert!(cache.get(source_hash(&script(1))).is_some());
+        assert!(cache.get(source_hash(&script(3))).is_some());
+
+        // 캐시에 있으면 다시 컴파일하지 않습니다.
+        let again = cache.get_or_insert(&script(1), |_| Err(())).unwrap();
+        assert!(Rc::ptr_eq(&again, &one));
+    }
+
+    #[test]
+    fn failed_compiles_are_not_cached() {
+        let mut cache = ScriptCache::new(2);
+        assert!(cache.get_or_insert(&script(1), |_| Err(())).is_err());
+        assert!(cache.get(source_hash(&script(1))).is_none());
+    }
+
+    #[test]
+    fn same_hash_with_another_source_is_recompiled() {
+        let mut cache = ScriptCache::new(2);
+        // 2의 해시를 단 1을 넣어 해시 충돌을 흉내 냅니다.
+        let forged = cache
+            .get_or_insert(&script(1), |source| {
+                let mut forged = CompiledScript::new(source);
+                forged.hash = source_hash(&script(2));
+                Ok::<_, ()>(forged)
+            })
+            .unwrap();
+        assert_eq!(forged.source(), script(1));
+
+        let real = cache.get_or_insert(&script(2), compile).unwrap();
+        assert_eq!(real.source(), script(2));
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.get(source_hash(&script(2))).unwrap().source(), script(2));
+    }
+}
@@ -0,0 +1,171 @@
+use serde::Serialize;
+
+use super::ChessemblyCompiled;
+
+/// 스크립트의 문제 하나.
+/// `ChessemblyCompiled::from_script`는 잘못된 명령도 조용히 `end`나 0으로 읽으므로, 컴파일하기 전에 확인합니다.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ScriptDiagnostic {
+    /// 1부터 센 줄 번호
+    pub line: usize,
+    /// 문제가 있는 조각 (명령 하나, 또는 체인의 앞부분)
+    pub fragment: String,
+    pub message: String,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Param {
+    /// 기물 이름이나 레지스터 이름
+    Name,
+    /// -128~127 (칸 수, 반복 횟수)
+    Delta,
+    /// 0~255 (레이블, 레지스터 값)
+    Byte,
+    /// 폰 단위의 실수 (`value`)
+    Pawns,
+}
+
+const KEYWORDS: &[&str] = &["end", "while", "do", "not", "check", "transition", "{", "}"];
+
+fn params_of(cmd: &str) -> Option<&'static [Param]> {
+    use Param::*;
+    Some(match cmd {
+        "label" | "jmp" | "jne" => &[Byte],
+        "repeat" => &[Delta],
+        "transition" | "piece" => &[Name],
+        "set-state" | "if-state" => &[Name, Byte],
+        "value" => &[Name, Pawns],
+        "piece-on" => &[Name, Delta, Delta],
+        "take-move" | "take" | "jump" | "move" | "catch" | "danger" | "enemy" | "friendly" | "peek" | "observe"
+        | "bound" | "edge" | "corner" | "edge-left" | "edge-right" | "edge-top" | "edge-bottom" | "corner-top-left"
+        | "corner-top-right" | "corner-bottom-left" | "corner-bottom-right" => &[Delta, Delta],
+        _ => return None,
+    })
+}
+
+fn check_param(param: Param, text: &str) -> bool {
+    match param {
+        Param::Name => !text.is_empty(),
+        Param::Delta => text.parse::<i8>().is_ok(),
+        Param::Byte => text.parse::<u8>().is_ok(),
+        Param::Pawns => text.parse::<f64>().is_ok_and(f64::is_finite),
+    }
+}
+
+/// 명령 하나를 확인합니다. 문제가 없으면 `None`입니다.
+fn check_fragment(fragment: &str) -> Option<String> {
+    if KEYWORDS.contains(&fragment) {
+        return None;
+    }
+    if fragment.starts_with('{') || fragment.starts_with('}') {
+        return Some("중괄호 뒤에는 공백이 있어야 합니다".to_string());
+    }
+    let Some((cmd, rest)) = fragment.split_once('(') else {
+        return Some(format!("알 수 없는 명령: {fragment}"));
+    };
+    let Some(params) = params_of(cmd.trim()) else {
+        return Some(format!("알 수 없는 명령: {}", cmd.trim()));
+    };
+    let Some((args, trailing)) = rest.split_once(')') else {
+        return Some("닫는 괄호가 없습니다".to_string());
+    };
+    if !trailing.trim().is_empty() {
+        return Some(format!("닫는 괄호 뒤에 알 수 없는 글자가 있습니다: {}", trailing.trim()));
+    }
+    let args: Vec<&str> = args.split(',').map(str::trim).collect();
+    if args.len() != params.len() {
+        return Some(format!("{}에는 인자가 {}개 필요합니다 ({}개)", cmd.trim(), params.len(), args.len()));
+    }
+    params
+        .iter()
+        .zip(&args)
+        .find(|(param, arg)| !check_param(**param, arg))
+        .map(|(_, arg)| format!("{}의 인자가 올바르지 않습니다: {arg}", cmd.trim()))
+}
+
+/// `label(n)`, `jmp(n)`, `jne(n)`의 명령 이름과 n
+fn label_command(fragment: &str) -> Option<(&str, u8)> {
+    let (cmd, rest) = fragment.split_once('(')?;
+    let n = rest.split_once(')')?.0.trim().parse().ok()?;
+    Some((cmd.trim(), n))
+}
+
+/// 스크립트를 컴파일하기 전에 확인합니다. 문제가 없으면 빈 목록입니다.
+///
+/// 알 수 없는 명령, 인자의 개수와 형식, 닫는 괄호, 중괄호 짝, 체인 안에 없는 레이블로의 점프,
+/// 다른 명령과 섞인 `value`, 그리고 주석이 아닌 곳의 ASCII가 아닌 문자(컴파일러가 처리하지 못합니다)를 찾습니다.
+pub fn diagnose(script: &str) -> Vec<ScriptDiagnostic> {
+    let line_of = |text: &str| {
+        let offset = text.as_ptr() as usize - script.as_ptr() as usize;
+        script[..offset].matches('\n').count() + 1
+    };
+    let mut diagnostics = Vec::new();
+    for chain_str in ChessemblyCompiled::chains_of(script) {
+        let chain = chain_str.trim_start();
+        if !chain.is_ascii() {
+            diagnostics.push(ScriptDiagnostic {
+                line: line_of(chain),
+                fragment: chain.chars().take(40).collect(),
+                message: "주석이 아닌 곳에 ASCII가 아닌 문자가 있습니다".to_string(),
+            });
+            continue;
+        }
+
+        let fragments = ChessemblyCompiled::fragments_of(chain_str);
+        let mut report = |fragment: &str, message: String| {
+            diagnostics.push(ScriptDiagnostic {
+                line: line_of(fragment),
+                fragment: fragment.to_string(),
+                message,
+            });
+        };
+        let mut depth = 0i32;
+        let mut labels = Vec::new();
+        let mut jumps = Vec::new();
+        for fragment in &fragments {
+            if let Some(message) = check_fragment(fragment) {
+                report(fragment, message);
+                continue;
+            }
+            match *fragment {
+                "{" => depth += 1,
+                "}" if depth == 0 => report(fragment, "여는 중괄호가 없습니다".to_string()),
+                "}" => depth -= 1,
+                _ => match label_command(fragment) {
+                    Some(("label", n)) => labels.push(n),
+                    Some(("jmp" | "jne", n)) => jumps.push((*fragment, n)),
+                    _ => {}
+                },
+            }
+        }
+        if depth > 0 {
+            report(chain.lines().next().unwrap_or(chain), format!("닫는 중괄호가 {depth}개 모자랍니다"));
+        }
+        // 인터프리터는 value를 만나면 체인을 멈추므로, 다른 명령과 섞으면 뒤의 명령이 조용히 무시됩니다.
+        if fragments.len() > 1 {
+            if let Some(value) = fragments.iter().find(|fragment| fragment.starts_with("value(")) {
+                report(value, "value는 다른 명령 없이 혼자 체인이어야 합니다".to_string());
+            }
+        }
+        for (fragment, n) in jumps {
+            if !labels.contains(&n) {
+                report(fragment, format!("체인 안에 label({n})이 없습니다"));
+            }
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_must_be_its_own_chain() {
+        assert!(diagnose("value(knight, 3.25); piece(knight) take-move(1, 2);").is_empty());
+        let diagnostics = diagnose("piece(knight) value(knight, 3.25) take-move(1, 2);");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].fragment, "value(knight, 3.25)");
+        assert_eq!(diagnose("value(knight, 3.25) take-move(1, 2);").len(), 1);
+    }
+}
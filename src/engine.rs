@@ -6,16 +6,33 @@ pub mod game_logic {
     use chessembly::board::Board;
     use chessembly::board::BoardStatus;
     use chessembly::ChessMove;
-    use chessembly::Color;
     use chessembly::MoveGen;
+    use chessembly::MoveType;
+
+    use super::eval::{Evaluator, PositionalEvaluator, DEFAULT_EVALUATOR};
+    use super::tablebase::{Outcome, Tablebases};
+    use super::values::piece_value;
     // use crate::chess::{self, Board, ChessMove, Color, GameStatus, MoveGen, Piece};
 
+    /// 체크메이트 당한 쪽의 평가 점수는 `-MATE_SCORE`입니다.
+    /// 탐색은 여기에 루트로부터의 거리(플라이)를 더해 빠른 메이트와 느린 메이트를 구분합니다.
+    pub const MATE_SCORE: i32 = 1_000_000;
+
     /// 모든 게임의 '수'가 구현해야 하는 기본 트레이트.
     /// Debug와 Clone은 검색 트리에 필수적입니다.
-    pub trait GameMove: std::fmt::Debug + Clone {}
+    /// PartialEq는 트랜스포지션 테이블의 최선의 수를 찾는 데 씁니다.
+    pub trait GameMove: std::fmt::Debug + Clone + PartialEq {
+        /// 히스토리 휴리스틱 테이블의 (출발, 도착) 칸 인덱스. 둘 다 64 미만이어야 합니다.
+        fn squares(&self) -> (usize, usize);
+    }
 
     /// 'chess' 라이브러리의 ChessMove에 우리 트레이트를 구현.
-    impl<'a> GameMove for ChessMove<'a> {}
+    impl<'a> GameMove for ChessMove<'a> {
+        fn squares(&self) -> (usize, usize) {
+            let (from, to) = (self.get_source(), self.get_dest());
+            (from.1 as usize * 8 + from.0 as usize, to.1 as usize * 8 + to.0 as usize)
+        }
+    }
 
     /// 모든 게임 상태(보드)가 구현해야 하는 트레이트.
     /// 이 트레이트만 구현하면 어떤 게임이든 우리 검색 알고리즘을 쓸 수 있습니다.
@@ -27,11 +44,41 @@ pub mod game_logic {
         fn is_terminal(&self) -> bool;
         fn evaluate(&mut self) -> i32;
 
+        /// 트랜스포지션 테이블의 키로 쓰는 위치 해시.
+        /// 같은 위치(차례와 특수 상태 포함)는 항상 같은 값을 가져야 합니다.
+        fn position_hash(&self) -> u64;
+
         /// (추가됨) 수 정렬을 위한 휴리스틱 점수 반환
         /// 이 점수는 '평가(evaluate)'와 다릅니다. 이 수는 즉각적으로
         /// 얼마나 "공격적인" 수인지를 나타냅니다. (예: 캡처, 프로모션)
         /// 높을수록 먼저 탐색되어야 합니다.
         fn score_move(&self, m: &Self::Move) -> i32;
+
+        /// 정지 탐색(quiescence search)에서 더 볼 '시끄러운' 수인지 분류합니다.
+        /// 조용한 수면 `None`, 잡기나 승격(변신) 같은 수면 그 수로 얻는
+        /// 대략적인 기물 이득(evaluate와 같은 단위)을 반환합니다.
+        fn noisy_gain(&self, m: &Self::Move) -> Option<i32>;
+
+        /// 현재 차례인 쪽이 체크 상태인지 반환합니다.
+        fn is_check(&mut self) -> bool;
+
+        /// 차례만 넘긴 상태를 반환합니다. (널 무브 가지치기용)
+        fn make_null_move(&self) -> Self;
+
+        /// 현재 차례인 쪽에 폰과 킹이 아닌 기물이 있는지 반환합니다.
+        /// 없으면 추크추방(zugzwang) 위험이 커서 널 무브 가지치기를 하지 않습니다.
+        fn has_non_pawn_material(&self) -> bool;
+
+        /// 엔드게임 테이블베이스에서 찾은 현재 차례인 쪽의 점수. 메이트는 `MATE_SCORE - 플라이`로 나타냅니다.
+        /// 테이블이 없는 게임이나 위치는 `None`입니다.
+        fn tablebase_score(&self, _tables: &Tablebases) -> Option<i32> {
+            None
+        }
+
+        /// `evaluator`의 가중치로 한 평가. 그 가중치를 쓸 수 없는 게임은 `evaluate`와 같습니다.
+        fn evaluate_with(&mut self, _evaluator: &PositionalEvaluator) -> i32 {
+            self.evaluate()
+        }
     }
 
     // --- 표준 체스를 위한 GameState 구현 ---
@@ -56,54 +103,32 @@ pub mod game_logic {
             self.status() != BoardStatus::Ongoing
         }
 
+        fn position_hash(&self) -> u64 {
+            Board::position_hash(self)
+        }
+
         /// 현재 턴인 플레이어의 관점에서 보드 점수를 계산합니다.
         fn evaluate(&mut self) -> i32 {
             // 1. 게임 종료 상태 확인
             if self.is_terminal() {
                 return match self.status() {
                     // 현재 플레이어가 체크메이트 당함 (최악의 점수)
-                    BoardStatus::Checkmate => -1_000_000,
+                    BoardStatus::Checkmate => -MATE_SCORE,
                     // 무승부
                     BoardStatus::Stalemate => 0,
                     _ => 0,
                 };
             }
 
-            // 2. 기물 가치 계산 (단순한 예시)
-            let mut score = 0;
-            for i in 0..8 {
-                for j in 0..8 {
-                    if let Some(piece) = self.piece_on(&(i, j)) {
-                        let value = get_piece_value(piece);
-                        if self.color_on(&(i, j)) == Some(Color::White) {
-                            if self.side_to_move() == Color::White {
-                                score += value * 5;
-                            } else {
-                                score += value * 5;
-                            }
-                        } else {
-                            if self.side_to_move() == Color::White {
-                                score -= value * 5;
-                            } else {
-                                score -= value * 5;
-                            }
-                        }
-                    }
-                }
-            }
-
-            // let dz1 = MoveGen::get_danger_zones(self, Color::White).len();
-            // let dz2 = MoveGen::get_danger_zones(self, Color::Black).len();
-            // score += dz1 as i32 - dz2 as i32;
+            // 2. 기물 가치와 위치 평가 (다른 평가로 바꾸려면 `eval::Evaluated`로 감싸세요.)
+            DEFAULT_EVALUATOR.evaluate(self)
+        }
 
-            // 3. 현재 턴인 플레이어에 맞춰 점수 반환
-            // 백의 턴이면 (백 - 흑) 점수 반환
-            // 흑의 턴이면 (흑 - 백) 점수 반환
-            if self.side_to_move() == Color::White {
-                score
-            } else {
-                -score
+        fn evaluate_with(&mut self, evaluator: &PositionalEvaluator) -> i32 {
+            if self.is_terminal() {
+                return self.evaluate();
             }
+            evaluator.evaluate(self)
         }
 
         fn score_move(&self, m: &Self::Move) -> i32 {
@@ -112,7 +137,7 @@ pub mod game_logic {
             // 1. 프로모션: 퀸 프로모션이 가장 높은 점수를 가집니다.
             if let Some(promoted_piece) = m.get_promotion() {
                 // 기본 1000점에 + 프로모션 기물 가치
-                score += 50 + 5 * get_piece_value(promoted_piece);
+                score += 1000 + piece_value(self, promoted_piece);
             }
 
             // 2. 캡처 (기물 잡기)
@@ -127,44 +152,72 @@ pub mod game_logic {
                 // 예: 폰으로 퀸 잡기: (900 * 10) - 100 = 8900 점
                 // 예: 퀸으로 폰 잡기: (100 * 10) - 900 = 100 점
                 // 이렇게 하면 가치 높은 기물을 잡는 수가 압도적으로 높은 우선순위를 갖게 됩니다.
-                // score += (get_piece_value(victim) * 10) - get_piece_value(attacker);
-                score += get_piece_value(victim) * 50 - get_piece_value(attacker) * 5;
+                score += piece_value(self, victim) * 10 - piece_value(self, attacker);
             }
 
-            // 3. TODO (고급): 나중에는 'Killer Moves' (이전 컷오프를 유발한 조용한 수)
-            // 4. TODO (고급): 나중에는 'History Heuristic' (과거에 좋았던 수)
+            // 3. 'Killer Moves'와 'History Heuristic'은 탐색 중에만 알 수 있으므로
+            //    search 모듈에서 이 점수와 합쳐 정렬합니다.
 
             // 캡처나 프로모션이 아닌 '조용한 수(quiet move)'는 0점을 반환합니다.
             score
         }
-    }
 
-    /// 기물의 가치를 반환하는 헬퍼 함수
-    fn get_piece_value(piece: &str) -> i32 {
-        if piece == "pawn" {
-            return 1;
-        } else if piece == "knight" {
-            return 3;
-        } else if piece == "bishop" {
-            return 3;
-        } else if piece == "rook" {
-            return 5;
-        } else if piece == "queen" {
-            return 9;
-        } else if piece == "king" {
-            return 10000;
-        } else {
-            return 8;
+        fn noisy_gain(&self, m: &Self::Move) -> Option<i32> {
+            // take-move는 적 기물이 있는 칸으로 갈 때만 잡는 수입니다.
+            let captured = match m.move_type {
+                MoveType::Take | MoveType::TakeJump | MoveType::Catch => self.piece_on(&m.take),
+                MoveType::TakeMove if self.color_on(&m.take) == Some(self.side_to_move().invert()) => {
+                    self.piece_on(&m.take)
+                }
+                _ => None,
+            };
+            // 프로모션과 transition은 바뀐 기물과 원래 기물의 가치 차이만큼 이득으로 봅니다.
+            let promotion = m.get_promotion().map(|piece| {
+                piece_value(self, piece) - piece_value(self, self.piece_on(&m.get_source()).unwrap_or("pawn"))
+            });
+
+            if captured.is_none() && promotion.is_none() {
+                return None;
+            }
+            Some(captured.map_or(0, |piece| piece_value(self, piece)) + promotion.unwrap_or(0))
+        }
+
+        fn is_check(&mut self) -> bool {
+            // is_check(board, color)는 'color'의 기물이 상대 킹을 공격하는지 확인합니다.
+            let attacker = self.side_to_move().invert();
+            self.script.is_check(self, attacker)
         }
 
-        // match piece {
-        //     Piece::Pawn => 1,
-        //     Piece::Knight => 3,
-        //     Piece::Bishop => 3, // 비숍을 나이트보다 약간 높게 평가
-        //     Piece::Rook => 5,
-        //     Piece::Queen => 9,
-        //     Piece::King => 10000, // 킹은 사실상 무한대 가치지만, 평가에서는 제외
-        // }
+        fn make_null_move(&self) -> Self {
+            let mut ret = self.clone();
+            ret.dp.clear();
+            // 수를 두지 않았으므로 앙파상으로 잡을 수 있는 기회는 사라집니다.
+            ret.board_state.white.enpassant.clear();
+            ret.board_state.black.enpassant.clear();
+            ret.turn = ret.turn.invert();
+            // 차례를 넘긴 쪽이 둘 수가 없을 수 있으므로(스테일메이트) 다시 확인합니다.
+            ret.status = BoardStatus::Ongoing;
+            ret.update_status();
+            ret
+        }
+
+        fn has_non_pawn_material(&self) -> bool {
+            let turn = self.side_to_move();
+            (0..8u8).any(|i| {
+                (0..8u8).any(|j| {
+                    self.color_on(&(i, j)) == Some(turn)
+                        && !matches!(self.piece_on(&(i, j)), Some("pawn") | Some("king"))
+                })
+            })
+        }
+
+        fn tablebase_score(&self, tables: &Tablebases) -> Option<i32> {
+            Some(match tables.probe(self)? {
+                Outcome::Win(plies) => MATE_SCORE - plies as i32,
+                Outcome::Loss(plies) => -MATE_SCORE + plies as i32,
+                Outcome::Draw => 0,
+            })
+        }
     }
 }
 
@@ -172,10 +225,20 @@ pub mod game_logic {
 // 모듈 2: 알파-베타 검색 (네가맥스 구현)
 // -----------------------------------------------------------------------------
 pub mod search {
-    use super::game_logic::GameState;
+    use std::cmp::Reverse;
+
+    use serde::Serialize;
 
-    /// 지정된 깊이(depth)까지 탐색하여 최선의 수를 찾습니다.
-    /// (S: GameState)는 'GameState' 트레이트를 구현한 어떤 게임이든 받는다는 의미입니다.
+    use super::game_logic::{GameMove, GameState, MATE_SCORE};
+    use super::book::OpeningBook;
+    use super::eval::PositionalEvaluator;
+    use super::tablebase::Tablebases;
+    use super::mcts::{find_best_move_mcts, MctsConfig};
+    use super::rng::Rng;
+    use super::tt::{Bound, TranspositionTable};
+
+    // 지정된 깊이(depth)까지 탐색하여 최선의 수를 찾습니다.
+    // (S: GameState)는 'GameState' 트레이트를 구현한 어떤 게임이든 받는다는 의미입니다.
     // pub fn find_best_move<S: GameState>(state: &S, depth: u8) -> Option<(S::Move, i32)> {
     //     if state.is_terminal() {
     //         return None;
@@ -211,15 +274,15 @@ pub mod search {
     //     best_move.map(|m| (m, best_score))
     // }
 
-    /// 네가맥스(Negamax) 알고리즘을 사용한 알파-베타 가지치기 함수
-    ///
-    /// `alpha`: 현재 플레이어가 보장받을 수 있는 최소 점수 (하한선)
-    /// `beta`: 상대방이 허용하는 최대 점수 (상한선)
-    ///
-    /// 현재 노드의 점수가 `beta`보다 크거나 같으면,
-    /// 이 노드의 부모(상대방 턴)는 이 수를 절대 선택하지 않을 것입니다.
-    /// (상대방은 이미 `beta`보다 *낮은* 점수를 보장받았으므로)
-    /// 따라서 더 이상 탐색할 필요가 없습니다 (Beta Cut-off).
+    // 네가맥스(Negamax) 알고리즘을 사용한 알파-베타 가지치기 함수
+    //
+    // `alpha`: 현재 플레이어가 보장받을 수 있는 최소 점수 (하한선)
+    // `beta`: 상대방이 허용하는 최대 점수 (상한선)
+    //
+    // 현재 노드의 점수가 `beta`보다 크거나 같으면,
+    // 이 노드의 부모(상대방 턴)는 이 수를 절대 선택하지 않을 것입니다.
+    // (상대방은 이미 `beta`보다 *낮은* 점수를 보장받았으므로)
+    // 따라서 더 이상 탐색할 필요가 없습니다 (Beta Cut-off).
     // fn negamax<S: GameState>(state: &S, depth: u8, mut alpha: i32, beta: i32) -> i32 {
     //     // 1. 깊이 한계에 도달했거나 게임이 종료되었으면, 현재 상태를 평가하고 반환
     //     if depth == 0 || state.is_terminal() {
@@ -253,15 +316,494 @@ pub mod search {
 
     // ... in mod search
 
-    pub fn find_best_move<S: GameState>(state: &mut S, depth: u8) -> Result<(S::Move, i32), usize> {
+    /// 탐색 시간을 재는 시계.
+    /// 테스트나 네이티브 환경에서는 직접 만든 시계를 주입할 수 있습니다.
+    pub trait Clock {
+        /// 임의의 기준 시점으로부터 흐른 시간 (밀리초)
+        fn now_ms(&self) -> f64;
+    }
+
+    /// 기본 시계. wasm(Worker)에서는 JS의 `Date.now()`, 네이티브에서는 시스템 시간을 씁니다.
+    ///
+    /// Workers 런타임은 I/O가 없는 동안 `Date.now()`를 멈춰 두므로,
+    /// Worker 안에서는 시간 한도보다 노드 한도(`max_nodes`)가 실질적인 제한이 됩니다.
+    pub struct SystemClock;
+
+    impl Clock for SystemClock {
+        #[cfg(target_arch = "wasm32")]
+        fn now_ms(&self) -> f64 {
+            worker::js_sys::Date::now()
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        fn now_ms(&self) -> f64 {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs_f64() * 1000.0)
+                .unwrap_or(0.0)
+        }
+    }
+
+    /// 반복 심화(iterative deepening) 탐색의 한도.
+    /// 시간 한도는 탐색 시작 시점으로부터의 밀리초입니다.
+    #[derive(Clone)]
+    pub struct SearchLimits<'c> {
+        /// 최대 탐색 깊이
+        pub max_depth: u8,
+        /// 최대 노드 수. 넘으면 진행 중인 반복을 버리고 멈춥니다.
+        pub max_nodes: Option<u64>,
+        /// 소프트 한도: 이 시간이 지나면 새 반복을 시작하지 않습니다.
+        pub soft_deadline: Option<f64>,
+        /// 하드 한도: 이 시간이 지나면 진행 중인 반복도 버리고 멈춥니다.
+        pub hard_deadline: Option<f64>,
+        pub clock: &'c dyn Clock,
+    }
+
+    impl SearchLimits<'static> {
+        /// 노드/시간 제한 없이 `max_depth`까지만 탐색하는 한도
+        pub fn depth(max_depth: u8) -> Self {
+            SearchLimits {
+                max_depth,
+                max_nodes: None,
+                soft_deadline: None,
+                hard_deadline: None,
+                clock: &SystemClock,
+            }
+        }
+    }
+
+    impl Default for SearchLimits<'static> {
+        fn default() -> Self {
+            SearchLimits::depth(3)
+        }
+    }
+
+    /// 탐색 설정
+    ///
+    /// 선택적 탐색 기법은 각각 끌 수 있습니다. 변형 체스에서는 일반 체스의 가정
+    /// (예: 차례를 넘기는 것이 항상 손해라는 가정)이 틀릴 수 있으므로
+    /// 변형마다 효과를 측정해 보고 켜고 끄면 됩니다.
+    #[derive(Clone)]
+    pub struct SearchConfig<'c> {
+        pub limits: SearchLimits<'c>,
+        /// 트랜스포지션 테이블이 쓸 메모리 (바이트).
+        /// Worker의 128MB 메모리 한도를 생각해 기본값은 작게 잡습니다.
+        pub tt_bytes: usize,
+        /// 널 무브 가지치기: 차례를 넘겨도 베타를 넘으면 이 노드를 잘라냅니다.
+        pub null_move: bool,
+        /// 널 무브로 잘라내기 전에 얕은 일반 탐색으로 한 번 더 확인합니다. (추크추방 대비)
+        pub null_move_verification: bool,
+        /// 늦게 정렬된 조용한 수를 얕게 탐색하고, 좋아 보이면 다시 탐색합니다.
+        pub late_move_reductions: bool,
+        /// 체크 당한 노드는 한 수 더 깊게 탐색합니다.
+        pub check_extensions: bool,
+        /// 베타 컷오프를 낸 조용한 수를 킬러 수와 히스토리 점수로 기억해 먼저 탐색합니다.
+        /// 끄면 조용한 수는 `score_move` 순서로만 정렬합니다.
+        pub killers_and_history: bool,
+        /// 점수와 주 변화를 함께 돌려줄 상위 루트 수의 개수 (multi-PV). 1이면 최선의 수만.
+        pub multi_pv: usize,
+        /// 탐색 중 쓰는 모든 난수의 시드. 같은 시드와 입력이면 항상 같은 수를 둡니다.
+        pub seed: u64,
+        /// 지정하면 최선의 수와 이 점수 이내로 차이 나는 수 중에서 무작위로 둡니다.
+        pub root_variety: Option<i32>,
+        /// `root_variety` 이내의 수를 고를 때 최선의 수보다 `gap`만큼 못한 수의 가중치를
+        /// `exp(-gap / 온도)`로 줍니다. `None`이면 똑같은 확률로 고릅니다.
+        pub root_temperature: Option<i32>,
+        /// 정적 평가에 더할 잡음의 최대 크기 (센티폰). 같은 위치와 시드에서는 항상 같은 잡음입니다.
+        pub eval_noise: i32,
+        /// 이 확률로 최선의 수 대신 `BLUNDER_MARGIN` 이내로 못한 다른 수를 둡니다. (실수 흉내)
+        pub blunder_rate: f64,
+        /// 탐색 방식. 한도와 결과 형식은 어느 쪽이든 같습니다.
+        pub backend: SearchBackend,
+        /// 탐색하기 전에 찾아볼 오프닝 북. 같은 스크립트로 만든 북이어야 합니다.
+        pub book: Option<&'c OpeningBook>,
+        /// 기물 구성이 맞으면 탐색 대신 결과를 찾아볼 엔드게임 테이블. 같은 스크립트로 만든 것이어야 합니다.
+        pub tablebases: Option<&'c Tablebases>,
+        /// 정적 평가에 쓸 가중치 (`bin/tune`으로 맞춘 것). 같은 스크립트로 맞춘 것이어야 합니다.
+        /// `None`이면 상태의 `evaluate`를 그대로 씁니다.
+        pub evaluator: Option<&'c PositionalEvaluator>,
+        /// 수 생성과 평가에 쓴 시간을 `SearchStats`에 잽니다. 노드마다 시계를 읽으므로 조금 느려집니다.
+        pub profile: bool,
+    }
+
+    /// 탐색 방식
+    #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    pub enum SearchBackend {
+        /// 반복 심화 알파-베타 (기본)
+        #[default]
+        AlphaBeta,
+        /// 몬테카를로 트리 탐색. 갈래가 아주 많거나 평가 함수가 잘 맞지 않는 변형에 씁니다.
+        Mcts(MctsConfig),
+    }
+
+    impl Default for SearchConfig<'static> {
+        fn default() -> Self {
+            SearchConfig {
+                limits: SearchLimits::default(),
+                tt_bytes: 4 << 20,
+                null_move: true,
+                null_move_verification: true,
+                late_move_reductions: true,
+                check_extensions: true,
+                killers_and_history: true,
+                multi_pv: 1,
+                seed: 0,
+                root_variety: None,
+                root_temperature: None,
+                eval_noise: 0,
+                blunder_rate: 0.0,
+                backend: SearchBackend::AlphaBeta,
+                book: None,
+                tablebases: None,
+                evaluator: None,
+                profile: false,
+            }
+        }
+    }
+
+    /// 실수할 때 고르는 수가 최선의 수보다 못할 수 있는 최대 점수 차이
+    pub const BLUNDER_MARGIN: i32 = 300;
+
+    /// 루트로부터의 최대 플라이
+    const MAX_PLY: usize = 256;
+    /// 절댓값이 이 이상인 점수는 메이트 점수입니다.
+    const MATE_BOUND: i32 = MATE_SCORE - MAX_PLY as i32;
+
+    /// 루트 기준 메이트 점수를 현재 노드 기준으로 바꿉니다. (트랜스포지션 테이블 저장용)
+    /// 같은 위치라도 루트로부터의 거리가 다르면 메이트까지의 거리도 다르기 때문입니다.
+    fn to_node_relative(score: i32, ply: usize) -> i32 {
+        if score >= MATE_BOUND {
+            score + ply as i32
+        } else if score <= -MATE_BOUND {
+            score - ply as i32
+        } else {
+            score
+        }
+    }
+
+    /// 현재 노드 기준 메이트 점수(테이블 항목, 종료 상태의 평가)를 루트 기준으로 바꿉니다.
+    fn to_root_relative(score: i32, ply: usize) -> i32 {
+        if score >= MATE_BOUND {
+            score - ply as i32
+        } else if score <= -MATE_BOUND {
+            score + ply as i32
+        } else {
+            score
+        }
+    }
+
+    /// 메이트 점수면 몇 수 만에 메이트인지 반환합니다.
+    /// 양수면 현재 차례인 쪽이 메이트시키고, 음수면 메이트 당합니다.
+    pub fn mate_in(score: i32) -> Option<i32> {
+        if score >= MATE_BOUND {
+            Some((MATE_SCORE - score + 1) / 2)
+        } else if score <= -MATE_BOUND {
+            Some(-(MATE_SCORE + score) / 2)
+        } else {
+            None
+        }
+    }
+
+    /// 어스피레이션 윈도우의 초기 반폭 (이전 반복 점수 ± 이 값)
+    const ASPIRATION_WINDOW: i32 = 50;
+    /// 델타 가지치기 여유값: 잡아도 알파에 이만큼도 못 미치면 그 수는 보지 않습니다.
+    const DELTA_MARGIN: i32 = 200;
+    /// 정지 탐색의 최대 깊이. 기물이 서로 변신만 반복하는 스크립트에서도 끝나도록 합니다.
+    const MAX_QUIESCENCE_DEPTH: u8 = 8;
+
+    // 수 정렬 단계: 시끄러운 수 > 킬러 수 > 히스토리 점수 순으로 탐색합니다.
+    const NOISY_ORDER: i32 = 1 << 24;
+    const KILLER_ORDER: i32 = 1 << 22;
+    /// 히스토리 점수가 이 값을 넘으면 테이블 전체를 절반으로 줄입니다.
+    const HISTORY_MAX: i32 = 1 << 20;
+    const HISTORY_SQUARES: usize = 64;
+
+    /// 널 무브 가지치기를 시도하는 최소 깊이
+    const NULL_MOVE_MIN_DEPTH: u8 = 3;
+    /// 이 순서(0부터) 이후의 조용한 수부터 깊이를 줄입니다.
+    const LMR_FULL_DEPTH_MOVES: usize = 3;
+    const LMR_MIN_DEPTH: u8 = 3;
+
+    /// 한 번의 탐색 동안 유지되는 상태
+    struct SearchContext<'l, 'c, S: GameState> {
+        config: &'l SearchConfig<'c>,
+        limits: &'l SearchLimits<'c>,
+        tt: TranspositionTable<S::Move>,
+        /// 현재 반복의 깊이. 체크 연장은 루트에서 이 값의 두 배 플라이까지만 합니다.
+        root_depth: u8,
+        start: f64,
+        nodes: u64,
+        stopped: bool,
+        /// 루트 수 하나의 점수가 나오기 전에는 중단하지 않습니다. (반환할 수가 있어야 하므로)
+        can_stop: bool,
+        /// 중단된 반복에서 끝까지 점수를 낸 루트 수들. 첫 반복이 중단되면 이 중에서 고릅니다.
+        interrupted: Vec<RootLine<S::Move>>,
+        /// 플라이별 킬러 수 두 개: 같은 깊이의 형제 노드에서 베타 컷오프를 낸 조용한 수
+        killers: Vec<[Option<S::Move>; 2]>,
+        /// (출발, 도착) 칸별 히스토리 점수: 컷오프를 낸 조용한 수마다 depth²씩 쌓입니다.
+        history: Vec<i32>,
+        /// 삼각형 주 변화 테이블: `pv_table[ply]`는 그 플라이부터의 최선의 진행입니다.
+        pv_table: Vec<Vec<S::Move>>,
+        /// 이번 탐색에서 정확한 점수가 필요한 범위. 실수하기로 했으면 `config.root_variety`보다 넓습니다.
+        root_variety: Option<i32>,
+        stats: SearchStats,
+    }
+
+    impl<'l, 'c, S: GameState> SearchContext<'l, 'c, S> {
+        fn new(config: &'l SearchConfig<'c>) -> Self {
+            let limits = &config.limits;
+            SearchContext {
+                config,
+                limits,
+                tt: TranspositionTable::with_memory(config.tt_bytes),
+                root_depth: 0,
+                start: limits.clock.now_ms(),
+                nodes: 0,
+                stopped: false,
+                can_stop: false,
+                interrupted: Vec::new(),
+                killers: Vec::new(),
+                history: vec![0; HISTORY_SQUARES * HISTORY_SQUARES],
+                pv_table: Vec::new(),
+                root_variety: config.root_variety,
+                stats: SearchStats::default(),
+            }
+        }
+
+        /// `profile`을 켰을 때만 시계를 읽습니다.
+        fn profile_start(&self) -> Option<f64> {
+            self.config.profile.then(|| self.limits.clock.now_ms())
+        }
+
+        fn profile_since(&self, start: Option<f64>) -> f64 {
+            start.map_or(0.0, |t| self.limits.clock.now_ms() - t)
+        }
+
+        fn legal_moves(&mut self, state: &mut S) -> Vec<S::Move> {
+            let start = self.profile_start();
+            let moves = state.get_legal_moves();
+            self.stats.movegen_ms += self.profile_since(start);
+            moves
+        }
+
+        fn play(&mut self, state: &S, m: &S::Move) -> S {
+            let start = self.profile_start();
+            let child = state.make_move(m);
+            self.stats.movegen_ms += self.profile_since(start);
+            child
+        }
+
+        /// 테이블베이스에 있는 위치의 점수 (현재 노드 기준)
+        fn tablebase_score(&self, state: &S) -> Option<i32> {
+            self.config.tablebases.and_then(|tables| state.tablebase_score(tables))
+        }
+
+        /// 끝나지 않은 위치의 정적 평가. `eval_noise`가 있으면 위치와 시드로 정한 잡음을 더합니다.
+        fn static_eval(&mut self, state: &mut S) -> i32 {
+            let start = self.profile_start();
+            let score = match self.config.evaluator {
+                Some(evaluator) => state.evaluate_with(evaluator),
+                None => state.evaluate(),
+            };
+            self.stats.eval_ms += self.profile_since(start);
+            let noise = self.config.eval_noise;
+            if noise <= 0 {
+                return score;
+            }
+            let mut rng = Rng::new(state.position_hash() ^ self.config.seed);
+            score + rng.below(2 * noise as usize + 1) as i32 - noise
+        }
+
+        /// 이 플라이의 주 변화를 비웁니다. 자식(ply + 1)의 칸도 함께 마련해 둡니다.
+        fn clear_pv(&mut self, ply: usize) {
+            if self.pv_table.len() < ply + 2 {
+                self.pv_table.resize_with(ply + 2, Vec::new);
+            }
+            self.pv_table[ply].clear();
+        }
+
+        /// 이 플라이의 주 변화를 `m` + 자식의 주 변화로 갱신합니다.
+        fn update_pv(&mut self, ply: usize, m: &S::Move) {
+            let (head, tail) = self.pv_table.split_at_mut(ply + 1);
+            head[ply].clear();
+            head[ply].push(m.clone());
+            head[ply].extend(tail[0].iter().cloned());
+        }
+
+        fn history_index(m: &S::Move) -> Option<usize> {
+            let (from, to) = m.squares();
+            (from < HISTORY_SQUARES && to < HISTORY_SQUARES).then_some(from * HISTORY_SQUARES + to)
+        }
+
+        /// 수 정렬 점수: 기존 `score_move`에 킬러/히스토리 점수를 합칩니다.
+        fn order_score(&self, state: &S, m: &S::Move, ply: usize) -> i32 {
+            let score = state.score_move(m);
+            if state.noisy_gain(m).is_some() {
+                return NOISY_ORDER + score;
+            }
+            if !self.config.killers_and_history {
+                return score;
+            }
+            if let Some(slot) = self
+                .killers
+                .get(ply)
+                .and_then(|killers| killers.iter().position(|k| k.as_ref() == Some(m)))
+            {
+                return KILLER_ORDER - slot as i32;
+            }
+            score + Self::history_index(m).map_or(0, |i| self.history[i])
+        }
+
+        /// 조용한 수가 베타 컷오프를 냈을 때 킬러 수와 히스토리 테이블을 갱신합니다.
+        fn record_cutoff(&mut self, m: &S::Move, ply: usize, depth: u8) {
+            if !self.config.killers_and_history {
+                return;
+            }
+            if self.killers.len() <= ply {
+                self.killers.resize_with(ply + 1, || [None, None]);
+            }
+            let killers = &mut self.killers[ply];
+            if killers[0].as_ref() != Some(m) {
+                killers[1] = killers[0].replace(m.clone());
+            }
+
+            if let Some(i) = Self::history_index(m) {
+                self.history[i] += depth as i32 * depth as i32;
+                if self.history[i] > HISTORY_MAX {
+                    self.history.iter_mut().for_each(|h| *h /= 2);
+                }
+            }
+        }
+
+        fn elapsed(&self) -> f64 {
+            self.limits.clock.now_ms() - self.start
+        }
+
+        /// 반복마다 누적한 노드 수로 분기 계수를 구하고 통계를 마무리합니다.
+        fn finish_stats(&self, iteration_nodes: &[u64], depth: u8) -> SearchStats {
+            let mut stats = self.stats.clone();
+            stats.nodes = self.nodes;
+            stats.depth = depth;
+            stats.elapsed_ms = self.elapsed();
+            if stats.beta_cutoffs > 0 {
+                stats.first_move_cutoff_rate = stats.first_move_cutoffs as f64 / stats.beta_cutoffs as f64;
+            }
+            if let [.., a, b, c] = iteration_nodes {
+                if b > a {
+                    stats.branching_factor = (c - b) as f64 / (b - a) as f64;
+                }
+            } else if let [a, b] = iteration_nodes {
+                if *a > 0 {
+                    stats.branching_factor = (b - a) as f64 / *a as f64;
+                }
+            }
+            stats
+        }
+
+        /// 노드 한도나 하드 한도를 넘었는지 확인하고, 넘었으면 탐색을 멈춥니다.
+        fn should_stop(&mut self) -> bool {
+            if self.stopped || !self.can_stop {
+                return self.stopped;
+            }
+            if self.limits.max_nodes.is_some_and(|max| self.nodes >= max)
+                || self.limits.hard_deadline.is_some_and(|t| self.elapsed() >= t)
+            {
+                self.stopped = true;
+            }
+            self.stopped
+        }
+    }
+
+    /// 루트 수 하나의 탐색 결과
+    #[derive(Clone, Debug, Serialize)]
+    pub struct RootLine<M> {
+        #[serde(rename = "move")]
+        pub root_move: M,
+        pub score: i32,
+        /// 메이트 점수일 때 몇 수 만에 메이트인지 (`mate_in` 참고)
+        pub mate_in: Option<i32>,
+        /// 이 수부터 시작하는 예상 진행 (root_move 포함)
+        pub pv: Vec<M>,
+    }
+
+    /// 탐색 결과
+    #[derive(Clone, Debug, Serialize)]
+    pub struct SearchResult<M> {
+        #[serde(rename = "move")]
+        pub best_move: M,
+        pub score: i32,
+        /// 메이트 점수일 때 몇 수 만에 메이트인지 (`mate_in` 참고)
+        pub mate_in: Option<i32>,
+        /// 끝까지 마친 마지막 반복의 깊이
+        pub depth: u8,
+        /// 최선의 수부터 시작하는 주 변화(principal variation)
+        pub pv: Vec<M>,
+        /// 점수가 높은 순으로 정렬된 상위 `multi_pv`개의 루트 수. 첫 줄이 최선의 수입니다.
+        pub lines: Vec<RootLine<M>>,
+        /// 오프닝 북에서 고른 수인지. 이때는 탐색하지 않았으므로 점수는 0, 깊이는 0입니다.
+        pub book: bool,
+        /// 탐색 통계. 응답을 가볍게 하려고 직렬화하지 않습니다.
+        #[serde(skip)]
+        pub stats: SearchStats,
+    }
+
+    /// 탐색 통계
+    #[derive(Clone, Debug, Default, Serialize)]
+    pub struct SearchStats {
+        /// 탐색한 노드 수 (정지 탐색 포함)
+        pub nodes: u64,
+        /// 그중 정지 탐색 노드 수
+        pub qnodes: u64,
+        /// 트랜스포지션 테이블에서 같은 위치를 찾은 횟수
+        pub tt_hits: u64,
+        pub beta_cutoffs: u64,
+        /// 처음 탐색한 수에서 난 베타 컷오프 수
+        pub first_move_cutoffs: u64,
+        /// `first_move_cutoffs / beta_cutoffs`. 수 정렬이 좋을수록 1에 가깝습니다.
+        pub first_move_cutoff_rate: f64,
+        /// 유효 분기 계수: 마지막 반복의 노드 수를 그 전 반복의 노드 수로 나눈 값
+        pub branching_factor: f64,
+        pub elapsed_ms: f64,
+        /// 끝까지 마친 마지막 반복의 깊이
+        pub depth: u8,
+        /// 수 생성과 수 두기에 쓴 시간. `SearchConfig::profile`을 켰을 때만 잽니다.
+        pub movegen_ms: f64,
+        /// 정적 평가에 쓴 시간. `SearchConfig::profile`을 켰을 때만 잽니다.
+        pub eval_ms: f64,
+    }
+
+    /// `limits` 안에서 깊이를 1씩 늘려 가며 탐색하고,
+    /// 마지막으로 끝까지 마친 반복의 최선의 수와 점수, 주 변화를 반환합니다.
+    /// 둘째 반복부터는 이전 점수 주변의 좁은 창(aspiration window)으로 먼저 탐색합니다.
+    /// 첫 반복도 루트 수 하나의 점수가 나온 뒤에는 한도에서 멈추고, 그때까지 점수를 낸 수 중에서 고릅니다.
+    pub fn find_best_move<S: GameState>(
+        state: &mut S,
+        config: &SearchConfig,
+    ) -> Result<SearchResult<S::Move>, usize> {
+        let limits = &config.limits;
         if state.is_terminal() {
             return Err(260);
         }
-
-        let mut best_move = None;
-        let mut best_score = -i32::MAX;
-        let mut alpha = -i32::MAX;
-        let beta = i32::MAX;
+        if let Some(m) = config.book.and_then(|book| book.probe(state, config.seed)) {
+            return Ok(SearchResult {
+                best_move: m.clone(),
+                score: 0,
+                mate_in: None,
+                depth: 0,
+                pv: vec![m.clone()],
+                lines: vec![RootLine {
+                    root_move: m.clone(),
+                    score: 0,
+                    mate_in: None,
+                    pv: vec![m],
+                }],
+                book: true,
+                stats: SearchStats::default(),
+            });
+        }
+        if let SearchBackend::Mcts(mcts) = &config.backend {
+            return find_best_move_mcts(state, config, mcts);
+        }
 
         let mut moves = state.get_legal_moves();
         let n = moves.len();
@@ -269,66 +811,1038 @@ pub mod search {
         // --- (수 정렬 추가) ---
         // score_move 점수가 높은 순 (내림차순)으로 정렬합니다.
         // b가 a보다 앞에 오도록 비교합니다. (unstable_by가 더 빠름)
-        // worker::js_sys::Math::random()
+        // 점수가 같은 수들의 순서는 그대로 두어 같은 입력에는 항상 같은 결과가 나오게 합니다.
+        // (다양한 수를 두고 싶으면 `root_variety`를 쓰세요.)
+        moves.sort_by(|a, b| state.score_move(b).cmp(&state.score_move(a)));
+        // --- (끝) ---
 
-        let len = moves.len();
-        for i in (1..len).rev() {
-            let j = unsafe { (worker::js_sys::Math::random() * len as f64).to_int_unchecked() };
-            moves.swap(i, j);
+        let mut ctx = SearchContext::<S>::new(config);
+        // 실수할지는 탐색 전에 정합니다. 실수할 때는 고를 수 있는 수들의 점수도 정확해야 하기 때문입니다.
+        let mut rng = Rng::new(config.seed);
+        let blunder = config.blunder_rate > 0.0 && rng.next_f64() < config.blunder_rate;
+        if blunder {
+            ctx.root_variety = Some(config.root_variety.unwrap_or(0).max(BLUNDER_MARGIN));
         }
+        let mut best: Option<SearchResult<S::Move>> = None;
+        // 끝낸 반복마다 그때까지의 노드 수 (분기 계수 계산용)
+        let mut iteration_nodes: Vec<u64> = Vec::new();
 
-        moves.sort_by(|a, b| state.score_move(b).cmp(&state.score_move(a)));
-        // --- (끝) ---
+        for depth in 1..=limits.max_depth.max(1) {
+            if best.is_some() && limits.soft_deadline.is_some_and(|t| ctx.elapsed() >= t) {
+                break;
+            }
+
+            // multi-PV나 root_variety에서는 루트 수마다 정확한 점수가 필요하므로 좁은 창을 쓰지 않습니다.
+            // 메이트 점수 주변도 좁은 창이 의미가 없으므로 전체 창으로 탐색합니다.
+            let narrow = config.multi_pv <= 1 && ctx.root_variety.is_none();
+            let result = match &best {
+                Some(prev) if ASPIRATION_WINDOW > 0 && narrow && prev.score.abs() < MATE_BOUND => {
+                    aspiration_search(state, &moves, depth, prev.score, &mut ctx)
+                }
+                _ => search_root(state, &moves, depth, -i32::MAX, i32::MAX, &mut ctx),
+            };
+            // 중단된 반복의 결과는 믿을 수 없으므로 버립니다.
+            // 다만 첫 반복이 중단되었으면 그때까지 점수를 낸 루트 수들로 대신합니다.
+            let Some(mut lines) = result.or_else(|| {
+                let partial = std::mem::take(&mut ctx.interrupted);
+                (best.is_none() && !partial.is_empty()).then_some(partial)
+            }) else {
+                break;
+            };
+
+            // 상위 수들을 맨 앞으로 옮겨 다음 반복에서 가장 먼저 탐색합니다.
+            moves.sort_by_key(|m| lines.iter().position(|l| l.root_move == *m).unwrap_or(usize::MAX));
+            for line in &mut lines {
+                extend_pv(state, &mut line.pv, depth, &ctx);
+            }
+            let score = lines[0].score;
+            best = Some(SearchResult {
+                best_move: lines[0].root_move.clone(),
+                score,
+                mate_in: mate_in(score),
+                depth,
+                pv: lines[0].pv.clone(),
+                lines,
+                book: false,
+                stats: SearchStats::default(),
+            });
+            iteration_nodes.push(ctx.nodes);
+            if ctx.stopped {
+                break;
+            }
+
+            // 이 깊이 안에서 메이트가 정해졌으면 더 깊이 봐도 더 짧은 메이트는 없습니다.
+            if score.abs() >= MATE_BOUND && MATE_SCORE - score.abs() <= depth as i32 {
+                break;
+            }
+        }
+
+        let mut result = best.ok_or(n)?;
+        result.stats = ctx.finish_stats(&iteration_nodes, result.depth);
+
+        // 최선의 수와 root_variety 이내로 차이 나는 수 중에서 시드 난수로 하나를 고릅니다.
+        // 실수할 때는 최선의 수를 빼고 BLUNDER_MARGIN 이내의 수 중에서 똑같은 확률로 고릅니다.
+        if let Some(margin) = ctx.root_variety {
+            let threshold = result.score.saturating_sub(margin);
+            let candidates = result.lines.iter().take_while(|l| l.score >= threshold).count().max(1);
+            let index = match config.root_temperature {
+                _ if blunder && candidates > 1 => 1 + rng.below(candidates - 1),
+                Some(temperature) if !blunder && temperature > 0 => {
+                    let weights: Vec<f64> = result.lines[..candidates]
+                        .iter()
+                        .map(|l| (-(result.score.saturating_sub(l.score)) as f64 / temperature as f64).exp())
+                        .collect();
+                    let mut pick = rng.next_f64() * weights.iter().sum::<f64>();
+                    weights
+                        .iter()
+                        .position(|w| {
+                            pick -= w;
+                            pick < 0.0
+                        })
+                        .unwrap_or(candidates - 1)
+                }
+                _ => rng.below(candidates),
+            };
+            let chosen = &result.lines[index];
+            result.best_move = chosen.root_move.clone();
+            result.score = chosen.score;
+            result.mate_in = chosen.mate_in;
+            result.pv = chosen.pv.clone();
+        }
+        result.lines.truncate(config.multi_pv.max(1));
+
+        Ok(result)
+    }
+
+    /// 이전 반복의 점수 `prev` 주변 창으로 탐색하고, 창을 벗어나면 넓혀서 다시 탐색합니다.
+    fn aspiration_search<S: GameState>(
+        state: &S,
+        moves: &[S::Move],
+        depth: u8,
+        prev: i32,
+        ctx: &mut SearchContext<S>,
+    ) -> Option<Vec<RootLine<S::Move>>> {
+        let mut delta = ASPIRATION_WINDOW;
+        loop {
+            let alpha = prev.saturating_sub(delta).max(-i32::MAX);
+            let beta = prev.saturating_add(delta);
+            let lines = search_root(state, moves, depth, alpha, beta, ctx)?;
+
+            let score = lines[0].score;
+            let failed_low = score <= alpha && alpha > -i32::MAX;
+            let failed_high = score >= beta && beta < i32::MAX;
+            if !failed_low && !failed_high {
+                return Some(lines);
+            }
+            delta = delta.saturating_mul(4);
+        }
+    }
+
+    /// 루트의 수들을 주어진 창으로 탐색합니다.
+    /// 탐색이 중단되면 `None`, 아니면 점수가 높은 순으로 상위 `multi_pv`개의 수를 반환합니다.
+    /// 상위 `multi_pv`개가 찰 때까지는 창을 좁히지 않으므로 그 수들의 점수는 정확합니다.
+    fn search_root<S: GameState>(
+        state: &S,
+        moves: &[S::Move],
+        depth: u8,
+        alpha: i32,
+        beta: i32,
+        ctx: &mut SearchContext<S>,
+    ) -> Option<Vec<RootLine<S::Move>>> {
+        ctx.root_depth = depth;
+        let multi_pv = ctx.config.multi_pv.clamp(1, moves.len().max(1));
+        let mut lines: Vec<RootLine<S::Move>> = Vec::with_capacity(multi_pv + 1);
 
         for m in moves {
             // 정렬된 리스트를 사용합니다.
-            let mut new_state = state.make_move(&m);
+            let mut new_state = ctx.play(state, m);
 
-            let score = -negamax(&mut new_state, depth - 1, -beta, -alpha);
+            // 상위 목록이 찼으면 그 마지막 점수를 넘는 수만 관심 대상입니다.
+            // root_variety가 있으면 최선의 수와 그 이내로 차이 나는 수도 정확한 점수가 필요합니다.
+            let mut window_alpha = match lines.get(multi_pv - 1) {
+                Some(last) => alpha.max(last.score),
+                None => alpha,
+            };
+            if let (Some(margin), Some(best)) = (ctx.root_variety, lines.first()) {
+                window_alpha = window_alpha.min(alpha.max(best.score.saturating_sub(margin + 1)));
+            }
+            let score = -negamax(&mut new_state, depth - 1, 1, -beta, -window_alpha, true, ctx);
+            if ctx.stopped {
+                ctx.interrupted = lines;
+                return None;
+            }
+            ctx.can_stop = true;
 
-            if score > best_score {
-                best_score = score;
-                best_move = Some(m);
+            if lines.len() < multi_pv || score > window_alpha {
+                let mut pv = vec![m.clone()];
+                pv.extend(ctx.pv_table[1].iter().cloned());
+                let at = lines.partition_point(|l| l.score >= score);
+                lines.insert(
+                    at,
+                    RootLine {
+                        root_move: m.clone(),
+                        score,
+                        mate_in: mate_in(score),
+                        pv,
+                    },
+                );
+                let keep_above = ctx
+                    .root_variety
+                    .map_or(i32::MAX, |margin| lines[0].score.saturating_sub(margin));
+                let mut rank = 0;
+                lines.retain(|l| {
+                    rank += 1;
+                    rank <= multi_pv || l.score >= keep_above
+                });
+            }
+            if lines[0].score >= beta {
+                break;
             }
-            alpha = alpha.max(best_score);
         }
 
-        best_move.map(|m| (m, best_score)).ok_or(n)
+        Some(lines)
     }
 
-    fn negamax<S: GameState>(state: &mut S, depth: u8, mut alpha: i32, beta: i32) -> i32 {
-        if depth == 0 || state.is_terminal() {
-            return state.evaluate();
+    /// 트랜스포지션 테이블 컷오프로 짧아진 주 변화를 테이블의 최선의 수로 `depth`까지 이어 붙입니다.
+    fn extend_pv<S: GameState>(state: &S, pv: &mut Vec<S::Move>, depth: u8, ctx: &SearchContext<S>) {
+        let mut position = state.clone();
+        for m in pv.iter() {
+            position = position.make_move(m);
+        }
+        while pv.len() < depth as usize && !position.is_terminal() {
+            let Some(m) = ctx
+                .tt
+                .probe(position.position_hash())
+                .and_then(|entry| entry.best_move.clone())
+            else {
+                break;
+            };
+            if !position.get_legal_moves().contains(&m) {
+                break;
+            }
+            position = position.make_move(&m);
+            pv.push(m);
+        }
+    }
+
+    /// `ply`는 루트로부터의 거리로, 킬러 수 테이블의 인덱스로 씁니다.
+    /// `allow_null`이 거짓이면 널 무브를 시도하지 않습니다. (널 무브를 연달아 두지 않도록)
+    fn negamax<S: GameState>(
+        state: &mut S,
+        mut depth: u8,
+        ply: usize,
+        mut alpha: i32,
+        mut beta: i32,
+        allow_null: bool,
+        ctx: &mut SearchContext<S>,
+    ) -> i32 {
+        ctx.clear_pv(ply);
+        let config = ctx.config;
+        let in_check = (config.check_extensions || config.null_move || config.late_move_reductions)
+            && !state.is_terminal()
+            && state.is_check();
+
+        // 체크 연장: 체크를 당한 노드는 한 수 더 봅니다. 연속 체크로 끝없이 늘어나지 않게 제한합니다.
+        if in_check && config.check_extensions && ply < 2 * ctx.root_depth as usize {
+            depth += 1;
+        }
+
+        // 깊이 한계에서는 바로 평가하지 않고, 잡는 수가 끝날 때까지 정지 탐색으로 넘깁니다.
+        if depth == 0 {
+            return quiescence(state, alpha, beta, ply, 0, ctx);
+        }
+
+        ctx.nodes += 1;
+        if ctx.should_stop() {
+            return 0;
+        }
+
+        if state.is_terminal() {
+            return to_root_relative(state.evaluate(), ply);
+        }
+        // 테이블베이스에 있는 위치는 더 볼 필요가 없습니다. (루트는 수를 골라야 하므로 제외)
+        if ply > 0 {
+            if let Some(score) = ctx.tablebase_score(state) {
+                return to_root_relative(score, ply);
+            }
+        }
+        if ply >= MAX_PLY {
+            return ctx.static_eval(state);
+        }
+
+        // 메이트 거리 가지치기: 여기서 바로 메이트시켜도 이미 아는 더 빠른 메이트보다 못하면 볼 필요가 없습니다.
+        alpha = alpha.max(-MATE_SCORE + ply as i32);
+        beta = beta.min(MATE_SCORE - ply as i32 - 1);
+        if alpha >= beta {
+            return alpha;
+        }
+
+        // 트랜스포지션 테이블: 충분히 깊게 탐색한 적이 있으면 그 결과로 창을 좁히거나 바로 반환합니다.
+        let key = state.position_hash();
+        let mut tt_move = None;
+        if let Some(entry) = ctx.tt.probe(key) {
+            ctx.stats.tt_hits += 1;
+            if entry.depth >= depth {
+                let score = to_root_relative(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower => alpha = alpha.max(score),
+                    Bound::Upper => beta = beta.min(score),
+                }
+                if alpha >= beta {
+                    return score;
+                }
+            }
+            tt_move = entry.best_move.clone();
+        }
+        let alpha_orig = alpha;
+
+        // 널 무브 가지치기: 차례를 넘기고 얕게 탐색해도 베타를 넘으면 실제 수는 더 좋을 것으로 봅니다.
+        // 체크 중이거나, 바로 전에 널 무브를 했거나, 폰과 킹만 남아 추크추방 위험이 크면 하지 않습니다.
+        // 베타가 메이트 점수면 차례를 넘긴 결과로는 메이트를 증명할 수 없으므로 하지 않습니다.
+        if config.null_move
+            && allow_null
+            && depth >= NULL_MOVE_MIN_DEPTH
+            && !in_check
+            && beta.abs() < MATE_BOUND
+            && state.has_non_pawn_material()
+            && ctx.static_eval(state) >= beta
+        {
+            let reduction = if depth > 6 { 3 } else { 2 };
+            let mut null_state = state.make_null_move();
+            let null_depth = depth.saturating_sub(1 + reduction);
+            let score = -negamax(&mut null_state, null_depth, ply + 1, -beta, -beta + 1, false, ctx);
+            if ctx.stopped {
+                return 0;
+            }
+            if score >= beta {
+                // 널 무브로 얻은 메이트 점수는 믿을 수 없으므로 베타로 낮춥니다.
+                let score = if score >= MATE_BOUND { beta } else { score };
+                if !config.null_move_verification {
+                    return score;
+                }
+                // 검증 탐색: 널 무브 없이 같은 깊이로 실제 수를 봐서 추크추방이 아닌지 확인합니다.
+                let verified = negamax(state, null_depth, ply, beta - 1, beta, false, ctx);
+                if ctx.stopped {
+                    return 0;
+                }
+                if verified >= beta {
+                    return verified;
+                }
+            }
+            ctx.clear_pv(ply);
         }
 
         let mut value = -i32::MAX;
+        let mut best_move = None;
 
         // --- (수 정렬 추가) ---
         // 루트 노드(find_best_move)뿐만 아니라 모든 자식 노드에서도
         // 수 정렬을 수행해야 합니다.
-        let mut moves = state.get_legal_moves();
-        moves.sort_unstable_by(|a, b| state.score_move(b).cmp(&state.score_move(a)));
+        let mut moves = ctx.legal_moves(state);
+        // 종료로 표시되지 않은 위치라도 둘 수가 없으면 끝난 위치입니다.
+        if moves.is_empty() {
+            return if state.is_check() { -MATE_SCORE + ply as i32 } else { 0 };
+        }
+        moves.sort_by_cached_key(|m| Reverse(ctx.order_score(state, m, ply)));
+        // 테이블에 저장된 최선의 수를 가장 먼저 탐색합니다.
+        if let Some(i) = tt_move.and_then(|tm| moves.iter().position(|m| *m == tm)) {
+            moves[..=i].rotate_right(1);
+        }
         // --- (끝) ---
 
-        for m in moves {
+        for (i, m) in moves.into_iter().enumerate() {
             // 정렬된 리스트를 사용합니다.
-            let mut new_state = state.make_move(&m);
-            let score = -negamax(&mut new_state, depth - 1, -beta, -alpha);
-            value = value.max(score);
+            let mut new_state = ctx.play(state, &m);
+
+            // 늦은 수 줄이기(LMR): 늦게 정렬된 조용한 수는 얕은 널 윈도우로 먼저 보고,
+            // 알파를 넘을 때만 원래 깊이로 다시 탐색합니다.
+            let reduce = config.late_move_reductions
+                && depth >= LMR_MIN_DEPTH
+                && i >= LMR_FULL_DEPTH_MOVES
+                && !in_check
+                && state.noisy_gain(&m).is_none();
+            let mut score = alpha.saturating_add(1);
+            if reduce {
+                let reduction = if i >= 2 * LMR_FULL_DEPTH_MOVES && depth > 4 { 2 } else { 1 };
+                score = -negamax(
+                    &mut new_state,
+                    depth - 1 - reduction,
+                    ply + 1,
+                    -alpha - 1,
+                    -alpha,
+                    true,
+                    ctx,
+                );
+            }
+            if score > alpha {
+                score = -negamax(&mut new_state, depth - 1, ply + 1, -beta, -alpha, true, ctx);
+            }
+            if ctx.stopped {
+                return 0;
+            }
+            if score > value {
+                value = score;
+                if score > alpha {
+                    ctx.update_pv(ply, &m);
+                }
+                best_move = Some(m);
+            }
             alpha = alpha.max(value);
             if alpha >= beta {
-                // (이것이 킬러 수(Killer Move)가 됩니다.
-                // TODO: 'm'을 킬러 수 테이블에 저장)
+                ctx.stats.beta_cutoffs += 1;
+                if i == 0 {
+                    ctx.stats.first_move_cutoffs += 1;
+                }
+                // 조용한 수가 컷오프를 냈다면 킬러 수와 히스토리에 기록합니다.
+                if let Some(m) = best_move.as_ref().filter(|m| state.noisy_gain(m).is_none()) {
+                    ctx.record_cutoff(m, ply, depth);
+                }
                 break; // Beta Cut-off
             }
         }
 
         // worker::console_log!("{}", score);
 
+        let bound = if value <= alpha_orig {
+            Bound::Upper
+        } else if value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        ctx.tt.store(key, depth, bound, to_node_relative(value, ply), best_move);
+
+        value
+    }
+
+    /// 정지 탐색: 시끄러운 수(잡기, 승격)만 따라가서 수평선 효과를 막습니다.
+    ///
+    /// 현재 평가 점수(stand pat)를 하한으로 삼아, 그것만으로 `beta`를 넘으면 바로 끊고,
+    /// 기물을 잡아도 `alpha`에 닿지 못하는 수는 델타 가지치기로 건너뜁니다.
+    /// 체크 중에는 가만히 있을 수 없으므로 stand pat과 델타 가지치기 없이 모든 응수를 봅니다.
+    fn quiescence<S: GameState>(
+        state: &mut S,
+        mut alpha: i32,
+        beta: i32,
+        ply: usize,
+        qdepth: u8,
+        ctx: &mut SearchContext<S>,
+    ) -> i32 {
+        ctx.nodes += 1;
+        ctx.stats.qnodes += 1;
+        if ctx.should_stop() {
+            return 0;
+        }
+
+        if state.is_terminal() {
+            return to_root_relative(state.evaluate(), ply);
+        }
+        if let Some(score) = ctx.tablebase_score(state) {
+            return to_root_relative(score, ply);
+        }
+        let stand_pat = ctx.static_eval(state);
+        if qdepth >= MAX_QUIESCENCE_DEPTH {
+            return stand_pat;
+        }
+        let in_check = state.is_check();
+        if !in_check {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+        }
+
+        let mut moves: Vec<(S::Move, i32)> = ctx
+            .legal_moves(state)
+            .into_iter()
+            .filter_map(|m| match state.noisy_gain(&m) {
+                Some(gain) => Some((m, gain)),
+                None => in_check.then_some((m, 0)),
+            })
+            .collect();
+        if in_check && moves.is_empty() {
+            return -MATE_SCORE + ply as i32;
+        }
+        moves.sort_unstable_by_key(|(m, _)| Reverse(state.score_move(m)));
+
+        let mut value = if in_check { -i32::MAX } else { stand_pat };
+        for (m, gain) in moves {
+            if !in_check && stand_pat.saturating_add(gain).saturating_add(DELTA_MARGIN) < alpha {
+                continue;
+            }
+
+            let mut new_state = ctx.play(state, &m);
+            let score = -quiescence(&mut new_state, -beta, -alpha, ply + 1, qdepth + 1, ctx);
+            if ctx.stopped {
+                return 0;
+            }
+            value = value.max(score);
+            alpha = alpha.max(value);
+            if alpha >= beta {
+                break;
+            }
+        }
+
         value
     }
+
+    #[cfg(test)]
+    mod tests {
+        use std::cell::Cell;
+
+        use super::*;
+        use crate::chessembly::board::Board;
+        use crate::chessembly::{ChessemblyCompiled, Color};
+
+        const CHAMELEON: &str = include_str!("../chameleon.csm");
+
+        #[test]
+        fn mate_scores_are_stored_relative_to_the_node() {
+            // 루트에서 3플라이 아래 노드가 그로부터 2플라이 뒤에 메이트시킵니다.
+            let root_score = MATE_SCORE - 5;
+            let stored = to_node_relative(root_score, 3);
+            assert_eq!(stored, MATE_SCORE - 2);
+            assert_eq!(to_root_relative(stored, 3), root_score);
+            // 같은 위치를 더 깊은 곳에서 만나면 메이트도 그만큼 멉니다.
+            assert_eq!(to_root_relative(stored, 7), MATE_SCORE - 9);
+            assert_eq!(to_root_relative(to_node_relative(-root_score, 3), 7), -(MATE_SCORE - 9));
+            // 메이트가 아닌 점수는 그대로입니다.
+            assert_eq!(to_node_relative(250, 3), 250);
+            assert_eq!(to_root_relative(-250, 7), -250);
+        }
+
+        #[test]
+        fn mate_in_counts_moves() {
+            assert_eq!(mate_in(MATE_SCORE - 1), Some(1));
+            assert_eq!(mate_in(MATE_SCORE - 3), Some(2));
+            assert_eq!(mate_in(-(MATE_SCORE - 2)), Some(-1));
+            assert_eq!(mate_in(-(MATE_SCORE - 4)), Some(-2));
+            assert_eq!(mate_in(900), None);
+        }
+
+        #[test]
+        fn search_reports_mate_distance_through_the_table() {
+            let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+            // 백 킹 c6, 룩 b1, 흑 킹 a8: 1. Kc7 Ka7 2. Ra1#
+            let pieces = [
+                ((2, 2), "king", Color::White),
+                ((1, 7), "rook", Color::White),
+                ((0, 0), "king", Color::Black),
+            ];
+            let mut board = Board::from_pieces(&compiled, Color::White, &pieces);
+            let config = SearchConfig {
+                limits: SearchLimits::depth(5),
+                ..SearchConfig::default()
+            };
+            let result = find_best_move(&mut board, &config).unwrap();
+            assert_eq!(result.best_move.notation(), "c6c7");
+            assert_eq!(result.mate_in, Some(2));
+            assert_eq!(result.score, MATE_SCORE - 3);
+        }
+
+        #[test]
+        fn quiescence_sees_the_recapture_past_the_horizon() {
+            let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+            // 백 퀸 d1이 d5의 폰을 잡을 수 있지만 e6의 폰이 다시 잡습니다.
+            let pieces = [
+                ((4, 7), "king", Color::White),
+                ((3, 7), "queen", Color::White),
+                ((4, 0), "king", Color::Black),
+                ((3, 3), "pawn", Color::Black),
+                ((4, 2), "pawn", Color::Black),
+            ];
+            let mut board = Board::from_pieces(&compiled, Color::White, &pieces);
+            let config = SearchConfig {
+                limits: SearchLimits::depth(1),
+                ..SearchConfig::default()
+            };
+
+            // 정적 평가만 보면 폰을 잡는 수가 가장 좋아 보입니다.
+            let looks_best = board
+                .get_legal_moves()
+                .into_iter()
+                .max_by_key(|m| -board.make_move(m).evaluate())
+                .unwrap();
+            assert_eq!(looks_best.notation(), "d1d5");
+
+            // 깊이 1이라도 정지 탐색이 exd5를 보므로 퀸을 내주지 않습니다.
+            let result = find_best_move(&mut board, &config).unwrap();
+            assert_ne!(result.best_move.notation(), "d1d5");
+            let mut ctx = SearchContext::new(&config);
+            let mut after = board.make_move(&looks_best);
+            let recaptured = -quiescence(&mut after, -i32::MAX, i32::MAX, 1, 0, &mut ctx);
+            assert!(recaptured < result.score - 500, "{recaptured} vs {}", result.score);
+        }
+
+        #[test]
+        fn quiescence_does_not_stand_pat_in_check() {
+            let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+            // 백 나이트 c7이 흑 킹 e8에 체크를 걸면서 a8의 퀸을 노립니다.
+            // 흑은 기물로는 앞서지만 킹을 피하면 퀸을 잃습니다.
+            let pieces = [
+                ((4, 7), "king", Color::White),
+                ((2, 1), "knight", Color::White),
+                ((4, 0), "king", Color::Black),
+                ((0, 0), "queen", Color::Black),
+            ];
+            let mut board = Board::from_pieces(&compiled, Color::Black, &pieces);
+            assert!(board.is_check());
+            assert!(board.evaluate() > 0);
+
+            let config = SearchConfig::default();
+            let mut ctx = SearchContext::new(&config);
+            let score = quiescence(&mut board, -i32::MAX, i32::MAX, 0, 0, &mut ctx);
+            assert!(score < 0, "{score}");
+            // 좁은 창에서도 stand pat으로 베타를 넘었다고 끊지 않습니다.
+            let mut ctx = SearchContext::new(&config);
+            assert!(quiescence(&mut board, -1, 0, 0, 0, &mut ctx) < 0);
+        }
+
+        #[test]
+        fn killers_and_history_improve_move_ordering() {
+            let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+            let board = Board::new(&compiled);
+            let stats = |killers_and_history| {
+                let config = SearchConfig {
+                    limits: SearchLimits::depth(3),
+                    killers_and_history,
+                    ..SearchConfig::default()
+                };
+                find_best_move(&mut board.clone(), &config).unwrap().stats
+            };
+            // 같은 위치와 깊이에서 컷오프를 첫 수에서 더 자주 내고, 그만큼 노드도 덜 봅니다.
+            let (with, without) = (stats(true), stats(false));
+            assert!(with.nodes < without.nodes, "{} vs {}", with.nodes, without.nodes);
+            assert!(
+                with.first_move_cutoff_rate > without.first_move_cutoff_rate,
+                "{} vs {}",
+                with.first_move_cutoff_rate,
+                without.first_move_cutoff_rate
+            );
+        }
+
+        /// 테스트용 게임 트리. 위치는 `nodes`의 번호이고, 수는 옮겨 갈 위치의 번호입니다.
+        #[derive(Clone)]
+        struct Tree {
+            nodes: &'static [Node],
+            at: usize,
+        }
+
+        /// 트리의 위치 하나. 점수는 모두 그 위치에서 둘 차례인 쪽 기준입니다.
+        struct Node {
+            /// 둘 수 있는 수 (옮겨 갈 위치). 비었으면 끝난 위치입니다.
+            children: &'static [usize],
+            eval: i32,
+            /// 이 위치로 오는 수의 정렬 점수
+            order: i32,
+            check: bool,
+            /// 차례를 넘기면 옮겨 갈 위치
+            null: usize,
+        }
+
+        const fn node(children: &'static [usize], eval: i32, order: i32, null: usize) -> Node {
+            Node { children, eval, order, check: false, null }
+        }
+
+        #[derive(Clone, Debug, PartialEq)]
+        struct Step(usize);
+
+        impl GameMove for Step {
+            fn squares(&self) -> (usize, usize) {
+                (0, self.0 % HISTORY_SQUARES)
+            }
+        }
+
+        impl GameState for Tree {
+            type Move = Step;
+
+            fn get_legal_moves(&mut self) -> Vec<Step> {
+                self.nodes[self.at].children.iter().map(|&to| Step(to)).collect()
+            }
+
+            fn make_move(&self, m: &Step) -> Self {
+                Tree { at: m.0, ..self.clone() }
+            }
+
+            fn is_terminal(&self) -> bool {
+                self.nodes[self.at].children.is_empty()
+            }
+
+            fn evaluate(&mut self) -> i32 {
+                let node = &self.nodes[self.at];
+                match (node.children.is_empty(), node.check) {
+                    (true, true) => -MATE_SCORE,
+                    (true, false) => 0,
+                    _ => node.eval,
+                }
+            }
+
+            fn position_hash(&self) -> u64 {
+                self.at as u64
+            }
+
+            fn score_move(&self, m: &Step) -> i32 {
+                self.nodes[m.0].order
+            }
+
+            fn noisy_gain(&self, _m: &Step) -> Option<i32> {
+                None
+            }
+
+            fn is_check(&mut self) -> bool {
+                self.nodes[self.at].check
+            }
+
+            fn make_null_move(&self) -> Self {
+                Tree { at: self.nodes[self.at].null, ..self.clone() }
+            }
+
+            fn has_non_pawn_material(&self) -> bool {
+                true
+            }
+        }
+
+        /// 루트 수들을 반복 심화 없이 `depth`로 한 번만 탐색합니다.
+        /// 앞선 반복이 남긴 정렬과 창 없이 한 깊이의 동작만 보기 위해서입니다.
+        fn search_once(
+            nodes: &'static [Node],
+            depth: u8,
+            alpha: i32,
+            beta: i32,
+            config: &SearchConfig,
+        ) -> usize {
+            let root = Tree { nodes, at: 0 };
+            let mut moves = root.clone().get_legal_moves();
+            moves.sort_by_key(|m| Reverse(root.score_move(m)));
+            let mut ctx = SearchContext::new(config);
+            let lines = search_root(&root, &moves, depth, alpha, beta, &mut ctx).unwrap();
+            lines[0].root_move.0
+        }
+
+        #[test]
+        fn root_variety_is_reproducible_for_a_seed() {
+            // 점수가 조금씩 다른 루트 수 여섯 개. 모두 `root_variety` 안에 들어옵니다.
+            const SIX_MOVES: &[Node] = &[
+                node(&[1, 2, 3, 4, 5, 6], 0, 0, 0),
+                node(&[0], 0, 0, 0),
+                node(&[0], 10, 0, 0),
+                node(&[0], 20, 0, 0),
+                node(&[0], 30, 0, 0),
+                node(&[0], 40, 0, 0),
+                node(&[0], 50, 0, 0),
+            ];
+            let pick = |seed| {
+                let config = SearchConfig {
+                    limits: SearchLimits::depth(2),
+                    seed,
+                    root_variety: Some(200),
+                    eval_noise: 20,
+                    ..SearchConfig::default()
+                };
+                find_best_move(&mut Tree { nodes: SIX_MOVES, at: 0 }, &config).unwrap().best_move.0
+            };
+            let picks: Vec<usize> = (0..6).map(pick).collect();
+            for (seed, first) in picks.iter().enumerate() {
+                assert_eq!(&pick(seed as u64), first);
+            }
+            // 시드가 다르면 다른 수도 둡니다.
+            assert!(picks.iter().any(|m| m != &picks[0]), "{picks:?}");
+        }
+
+        #[test]
+        fn null_move_verification_keeps_a_zugzwang_move() {
+            // 1로 가면 상대(B)가 추크추방에 빠집니다: 정적 평가와 차례 넘기기로는 B가 좋아 보이지만
+            // 실제로 둘 수 있는 수(5)는 모두 집니다. 2는 무난한 +50입니다.
+            const ZUGZWANG: &[Node] = &[
+                node(&[1, 2], 0, 0, 0),
+                node(&[5], 100, 0, 7),
+                node(&[3], -50, 10, 3),
+                node(&[4], 50, 0, 4),
+                node(&[3], -50, 0, 3),
+                node(&[6], 900, 0, 6),
+                node(&[5], -900, 0, 5),
+                node(&[8], -100, 0, 8),
+                node(&[7], 100, 0, 7),
+            ];
+            let config = |null_move_verification| SearchConfig {
+                null_move_verification,
+                ..SearchConfig::default()
+            };
+            // 2를 먼저 본 뒤 1은 베타 근처의 좁은 창으로 봅니다. (어스피레이션 창과 같은 상황)
+            assert_eq!(search_once(ZUGZWANG, 5, 0, 100, &config(true)), 1);
+            // 검증하지 않으면 널 무브만 보고 1을 잘라내서 2를 둡니다.
+            assert_eq!(search_once(ZUGZWANG, 5, 0, 100, &config(false)), 2);
+        }
+
+        #[test]
+        fn check_extension_sees_a_mate_one_ply_past_the_depth() {
+            // 1은 체크이고 상대의 유일한 응수(3) 뒤에 조용한 수 4로 메이트입니다. 2는 아무 일도 없습니다.
+            const CHECK_THEN_MATE: &[Node] = &[
+                node(&[1, 2], 0, 0, 0),
+                Node { check: true, ..node(&[3], 0, 0, 1) },
+                node(&[6], 0, 0, 6),
+                node(&[4, 5], 0, 0, 5),
+                Node { check: true, ..node(&[], 0, 0, 4) },
+                node(&[6], 0, 0, 6),
+                node(&[5], 0, 0, 5),
+            ];
+            let search = |check_extensions| {
+                let config = SearchConfig {
+                    limits: SearchLimits::depth(2),
+                    check_extensions,
+                    ..SearchConfig::default()
+                };
+                find_best_move(&mut Tree { nodes: CHECK_THEN_MATE, at: 0 }, &config).unwrap()
+            };
+            let extended = search(true);
+            assert_eq!(extended.best_move, Step(1));
+            assert_eq!(extended.mate_in, Some(2));
+            // 연장하지 않으면 깊이 2에서는 세 번째 플라이의 메이트가 보이지 않습니다.
+            assert_eq!(search(false).mate_in, None);
+        }
+
+        #[test]
+        fn late_move_reductions_still_find_a_late_quiet_refutation() {
+            // 1로 가면 상대(B)에게 수가 여섯 개 있는데, 가장 늦게 정렬되는 8만 B가 이기는 수입니다.
+            // LMR이 8을 얕게 보더라도 알파를 넘으므로 다시 탐색해서 1을 피하고 2를 둬야 합니다.
+            const LATE_REFUTATION: &[Node] = &[
+                node(&[1, 2], 0, 0, 0),
+                node(&[3, 4, 5, 6, 7, 8], -200, 10, 13),
+                node(&[9], 0, 0, 9),
+                node(&[11], 200, 5, 11),
+                node(&[11], 200, 5, 11),
+                node(&[11], 200, 5, 11),
+                node(&[11], 200, 5, 11),
+                node(&[11], 200, 5, 11),
+                node(&[12], -500, 0, 12),
+                node(&[10], 0, 0, 10),
+                node(&[9], 0, 0, 9),
+                node(&[13], -200, 0, 13),
+                node(&[14], 500, 0, 14),
+                node(&[11], 200, 0, 11),
+                node(&[12], -500, 0, 12),
+            ];
+            let config = SearchConfig::default();
+            assert!(config.late_move_reductions);
+            assert_eq!(search_once(LATE_REFUTATION, 4, -i32::MAX, i32::MAX, &config), 2);
+        }
+
+        /// 읽을 때마다 `step` 밀리초씩 가는 시계
+        struct TickClock {
+            now: Cell<f64>,
+            step: f64,
+        }
+
+        impl TickClock {
+            fn new(step: f64) -> Self {
+                TickClock { now: Cell::new(0.0), step }
+            }
+        }
+
+        impl Clock for TickClock {
+            fn now_ms(&self) -> f64 {
+                let now = self.now.get();
+                self.now.set(now + self.step);
+                now
+            }
+        }
+
+        #[test]
+        fn hard_limits_stop_the_first_iteration_with_a_legal_move() {
+            let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+            let board = Board::new(&compiled);
+            let legal = board.clone().get_legal_moves();
+            let full = find_best_move(&mut board.clone(), &SearchConfig::default()).unwrap();
+            let first = find_best_move(
+                &mut board.clone(),
+                &SearchConfig {
+                    limits: SearchLimits::depth(1),
+                    ..SearchConfig::default()
+                },
+            )
+            .unwrap();
+
+            // 노드 한도가 1이어도 루트 수 하나는 끝까지 보고 멈춥니다.
+            let config = SearchConfig {
+                limits: SearchLimits {
+                    max_nodes: Some(1),
+                    ..SearchLimits::depth(6)
+                },
+                ..SearchConfig::default()
+            };
+            let result = find_best_move(&mut board.clone(), &config).unwrap();
+            assert!(legal.contains(&result.best_move));
+            assert_eq!(result.depth, 1);
+            assert!(result.stats.nodes < first.stats.nodes, "{} vs {}", result.stats.nodes, first.stats.nodes);
+
+            // 노드를 볼 때마다 1ms씩 가는 시계로 하드 한도를 넘깁니다.
+            let clock = TickClock::new(1.0);
+            let config = SearchConfig {
+                limits: SearchLimits {
+                    hard_deadline: Some(30.0),
+                    clock: &clock,
+                    ..SearchLimits::depth(6)
+                },
+                ..SearchConfig::default()
+            };
+            let result = find_best_move(&mut board.clone(), &config).unwrap();
+            assert!(legal.contains(&result.best_move));
+            assert!(result.stats.nodes < full.stats.nodes);
+            assert!(result.depth < 6);
+        }
+
+        #[test]
+        fn soft_deadline_finishes_the_iteration_in_progress() {
+            let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+            let board = Board::new(&compiled);
+            // 반복을 시작할 때마다 시계를 한 번 읽으므로 둘째 반복까지만 시작합니다.
+            let clock = TickClock::new(1.0);
+            let config = SearchConfig {
+                limits: SearchLimits {
+                    soft_deadline: Some(1.5),
+                    clock: &clock,
+                    ..SearchLimits::depth(6)
+                },
+                ..SearchConfig::default()
+            };
+            let result = find_best_move(&mut board.clone(), &config).unwrap();
+            assert_eq!((result.depth, result.stats.depth), (2, 2));
+            assert!(board.clone().get_legal_moves().contains(&result.best_move));
+        }
+
+        #[test]
+        fn stats_count_quiescence_table_hits_and_cutoffs() {
+            let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+            // 백 퀸 d1이 e6의 폰이 지키는 d5의 폰을 잡을 수 있습니다.
+            let mut capture = Board::from_pieces(
+                &compiled,
+                Color::White,
+                &[
+                    ((4, 7), "king", Color::White),
+                    ((3, 7), "queen", Color::White),
+                    ((4, 0), "king", Color::Black),
+                    ((3, 3), "pawn", Color::Black),
+                    ((4, 2), "pawn", Color::Black),
+                ],
+            );
+            let config = SearchConfig {
+                limits: SearchLimits::depth(1),
+                ..SearchConfig::default()
+            };
+            let stats = find_best_move(&mut capture, &config).unwrap().stats;
+            assert!(stats.qnodes > 0 && stats.qnodes <= stats.nodes, "{stats:?}");
+
+            let config = SearchConfig {
+                limits: SearchLimits::depth(3),
+                ..SearchConfig::default()
+            };
+            let stats = find_best_move(&mut Board::new(&compiled), &config).unwrap().stats;
+            assert_eq!(stats.depth, 3);
+            assert!(stats.tt_hits > 0, "{stats:?}");
+            assert!(stats.beta_cutoffs > 0 && stats.first_move_cutoffs <= stats.beta_cutoffs, "{stats:?}");
+            assert!(stats.branching_factor > 1.0, "{stats:?}");
+        }
+
+        #[test]
+        fn profile_timings_are_measured_only_when_asked() {
+            let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+            let board = Board::new(&compiled);
+            let clock = TickClock::new(1.0);
+            let stats = |profile| {
+                let config = SearchConfig {
+                    limits: SearchLimits {
+                        clock: &clock,
+                        ..SearchLimits::depth(2)
+                    },
+                    profile,
+                    ..SearchConfig::default()
+                };
+                find_best_move(&mut board.clone(), &config).unwrap().stats
+            };
+            let off = stats(false);
+            assert_eq!((off.movegen_ms, off.eval_ms), (0.0, 0.0));
+            let on = stats(true);
+            assert!(on.movegen_ms > 0.0 && on.eval_ms > 0.0, "{on:?}");
+        }
+
+        #[test]
+        fn aspiration_search_widens_the_window_when_the_score_jumps() {
+            // 깊이 1에서는 1이 +10으로 가장 좋지만, 깊이 2에서는 1이 -500, 2가 +200입니다.
+            // 둘째 반복은 +10 주변의 좁은 창에서 실패하고 넓혀서 다시 탐색해야 합니다.
+            const JUMP: &[Node] = &[
+                node(&[1, 2], 0, 0, 0),
+                node(&[3], -10, 0, 1),
+                node(&[4], -5, 0, 2),
+                node(&[0], -500, 0, 3),
+                node(&[0], 200, 0, 4),
+            ];
+            let config = SearchConfig {
+                limits: SearchLimits::depth(2),
+                ..SearchConfig::default()
+            };
+            let result = find_best_move(&mut Tree { nodes: JUMP, at: 0 }, &config).unwrap();
+            assert_eq!((result.best_move, result.score), (Step(2), 200));
+            assert_eq!(search_once(JUMP, 2, -i32::MAX, i32::MAX, &config), 2);
+        }
+    }
 }
 
+// -----------------------------------------------------------------------------
+// 모듈 3: 트랜스포지션 테이블
+// -----------------------------------------------------------------------------
+pub mod tt;
+
+// -----------------------------------------------------------------------------
+// 모듈 4: 재현 가능한 난수
+// -----------------------------------------------------------------------------
+pub mod rng;
+
+// -----------------------------------------------------------------------------
+// 모듈 5: 위치 평가
+// -----------------------------------------------------------------------------
+pub mod eval;
+
+// -----------------------------------------------------------------------------
+// 모듈 6: 스크립트 기물의 가치 추정
+// -----------------------------------------------------------------------------
+pub mod values;
+
+// -----------------------------------------------------------------------------
+// 모듈 7: 몬테카를로 트리 탐색
+// -----------------------------------------------------------------------------
+pub mod mcts;
+
+// -----------------------------------------------------------------------------
+// 모듈 8: 난이도
+// -----------------------------------------------------------------------------
+pub mod skill;
+
+// -----------------------------------------------------------------------------
+// 모듈 9: 오프닝 북
+// -----------------------------------------------------------------------------
+pub mod book;
+
+// -----------------------------------------------------------------------------
+// 모듈 10: 엔드게임 테이블베이스
+// -----------------------------------------------------------------------------
+pub mod tablebase;
+
+// -----------------------------------------------------------------------------
+// 모듈 11: 메이트 탐색
+// -----------------------------------------------------------------------------
+pub mod mate;
+
+// -----------------------------------------------------------------------------
+// 모듈 12: 루트 수 분석
+// -----------------------------------------------------------------------------
+pub mod analysis;
+
+// -----------------------------------------------------------------------------
+// 모듈 13: 대국 주석과 정확도
+// -----------------------------------------------------------------------------
+pub mod annotate;
+
 // -----------------------------------------------------------------------------
 // 메인 실행 함수
 // -----------------------------------------------------------------------------
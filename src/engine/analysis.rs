@@ -0,0 +1,181 @@
+use serde::Serialize;
+
+use super::game_logic::GameState;
+use super::search::{find_best_move, mate_in, SearchBackend, SearchConfig};
+
+/// 최선의 수보다 이만큼(센티폰) 이하로 잃으면 좋은 수입니다.
+pub const GOOD_LOSS: i32 = 30;
+/// 이만큼 이하로 잃으면 부정확한 수입니다.
+pub const INACCURACY_LOSS: i32 = 100;
+/// 이만큼 이하로 잃으면 실수, 더 잃으면 블런더입니다.
+pub const MISTAKE_LOSS: i32 = 300;
+
+/// 최선의 수와 비교한 수의 분류
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MoveClass {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl MoveClass {
+    /// 최선의 수보다 잃은 점수로 분류합니다.
+    /// 메이트를 놓치거나 메이트를 허용하면 점수 차이가 매우 크므로 블런더가 됩니다.
+    pub fn from_loss(loss: i32) -> MoveClass {
+        if loss <= 0 {
+            MoveClass::Best
+        } else if loss <= GOOD_LOSS {
+            MoveClass::Good
+        } else if loss <= INACCURACY_LOSS {
+            MoveClass::Inaccuracy
+        } else if loss <= MISTAKE_LOSS {
+            MoveClass::Mistake
+        } else {
+            MoveClass::Blunder
+        }
+    }
+}
+
+/// 루트 수 하나의 분석 결과
+#[derive(Clone, Debug, Serialize)]
+pub struct MoveAnalysis<M> {
+    #[serde(rename = "move")]
+    pub mv: M,
+    /// 이 수를 둔 쪽의 관점 점수 (센티폰)
+    pub score: i32,
+    /// 메이트 점수일 때 몇 수 만에 메이트인지 (`search::mate_in` 참고)
+    pub mate_in: Option<i32>,
+    /// 최선의 수보다 잃은 점수 (0 이상)
+    pub loss: i32,
+    pub class: MoveClass,
+    pub pv: Vec<M>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Analysis<M> {
+    /// 끝까지 마친 마지막 반복의 깊이. 모든 수의 점수가 이 깊이에서 나왔습니다.
+    pub depth: u8,
+    /// 점수가 높은 순으로 정렬된 모든 합법적인 수. 첫 수가 최선의 수입니다.
+    pub moves: Vec<MoveAnalysis<M>>,
+}
+
+/// 모든 합법적인 루트 수를 전체 창으로 탐색해 점수를 매기고, 최선의 수와 비교해 분류합니다.
+///
+/// `config`의 한도와 탐색 기법은 그대로 쓰지만, 분석 결과가 흔들리지 않도록
+/// 오프닝 북, 난이도 설정(잡음, 무작위 선택, 실수)은 끄고 알파-베타 탐색을 씁니다.
+/// 오류는 `find_best_move`와 같습니다.
+pub fn analyze<S: GameState>(state: &mut S, config: SearchConfig) -> Result<Analysis<S::Move>, usize> {
+    let config = SearchConfig {
+        multi_pv: usize::MAX,
+        root_variety: None,
+        root_temperature: None,
+        eval_noise: 0,
+        blunder_rate: 0.0,
+        book: None,
+        backend: SearchBackend::AlphaBeta,
+        ..config
+    };
+    let result = find_best_move(state, &config)?;
+    let best = result.score;
+    let moves = result
+        .lines
+        .into_iter()
+        .map(|line| {
+            let loss = best.saturating_sub(line.score).max(0);
+            MoveAnalysis {
+                mv: line.root_move,
+                score: line.score,
+                mate_in: mate_in(line.score),
+                loss,
+                class: MoveClass::from_loss(loss),
+                pv: line.pv,
+            }
+        })
+        .collect();
+    Ok(Analysis {
+        depth: result.depth,
+        moves,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chessembly::board::Board;
+    use crate::chessembly::{ChessemblyCompiled, Color};
+    use crate::engine::search::SearchLimits;
+
+    const CHAMELEON: &str = include_str!("../../chameleon.csm");
+
+    /// 백 킹 e1, 퀸 d1 대 흑 킹 e8, 폰 d5와 e6, 백 차례. Qxd5는 exd5로 퀸을 잃습니다.
+    fn hanging_queen<'a>(compiled: &'a ChessemblyCompiled<'a>) -> Board<'a> {
+        Board::from_pieces(
+            compiled,
+            Color::White,
+            &[
+                ((4, 7), "king", Color::White),
+                ((3, 7), "queen", Color::White),
+                ((4, 0), "king", Color::Black),
+                ((3, 3), "pawn", Color::Black),
+                ((4, 2), "pawn", Color::Black),
+            ],
+        )
+    }
+
+    /// 선택적 탐색 기법을 끈 고정 깊이 탐색. 루트 수마다 따로 탐색한 점수와 비교할 수 있습니다.
+    fn plain(depth: u8) -> SearchConfig<'static> {
+        SearchConfig {
+            limits: SearchLimits::depth(depth),
+            null_move: false,
+            late_move_reductions: false,
+            check_extensions: false,
+            ..SearchConfig::default()
+        }
+    }
+
+    #[test]
+    fn every_root_move_is_scored_and_sorted() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let mut board = hanging_queen(&compiled);
+        let analysis = analyze(&mut board, plain(2)).unwrap();
+        assert_eq!(analysis.depth, 2);
+
+        let legal = board.get_legal_moves();
+        assert_eq!(analysis.moves.len(), legal.len());
+        assert!(legal.iter().all(|m| analysis.moves.iter().any(|a| a.mv == *m)));
+        assert!(analysis.moves.windows(2).all(|w| w[0].score >= w[1].score));
+        assert_eq!(analysis.moves[0].loss, 0);
+        assert_eq!(analysis.moves[0].class, MoveClass::Best);
+
+        // 점수는 좁은 창으로 얻은 경계가 아니라, 그 수를 둔 뒤 따로 탐색한 값과 같은 정확한 점수입니다.
+        for line in &analysis.moves {
+            let mut child = board.make_move(&line.mv);
+            let alone = find_best_move(&mut child, &plain(1)).unwrap();
+            assert_eq!(line.score, -alone.score, "{}", line.mv.notation());
+            assert_eq!(line.loss, analysis.moves[0].score - line.score);
+        }
+    }
+
+    #[test]
+    fn hanging_the_queen_is_a_blunder() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let mut board = hanging_queen(&compiled);
+        let analysis = analyze(&mut board, plain(2)).unwrap();
+        let capture = analysis.moves.iter().find(|a| a.mv.notation() == "d1d5").unwrap();
+        assert_eq!(capture.class, MoveClass::Blunder);
+        assert!(capture.loss > MISTAKE_LOSS, "{}", capture.loss);
+        assert_ne!(analysis.moves[0].mv.notation(), "d1d5");
+    }
+
+    #[test]
+    fn losses_map_to_classes() {
+        assert_eq!(MoveClass::from_loss(0), MoveClass::Best);
+        assert_eq!(MoveClass::from_loss(GOOD_LOSS), MoveClass::Good);
+        assert_eq!(MoveClass::from_loss(INACCURACY_LOSS), MoveClass::Inaccuracy);
+        assert_eq!(MoveClass::from_loss(MISTAKE_LOSS), MoveClass::Mistake);
+        assert_eq!(MoveClass::from_loss(MISTAKE_LOSS + 1), MoveClass::Blunder);
+    }
+}
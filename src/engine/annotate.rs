@@ -0,0 +1,265 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::chessembly::board::{Board, BoardStatus, MoveParseError};
+use crate::chessembly::{ChessMove, Color};
+
+use super::analysis::{analyze, MoveClass};
+use super::game_logic::GameState;
+use super::search::SearchConfig;
+
+/// 평가 점수(센티폰)를 이길 확률로 바꾸는 로지스틱 곡선의 기울기
+const WIN_RATE_SLOPE: f64 = 0.003_682_08;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AnnotatedMove<'a> {
+    /// `moves`에서의 위치 (0부터)
+    pub ply: usize,
+    pub color: Color,
+    #[serde(rename = "move")]
+    pub mv: ChessMove<'a>,
+    /// 두기 전 평가: 최선의 수를 두었을 때의 점수 (백의 관점, 센티폰)
+    pub eval_before: i32,
+    /// 둔 수의 점수 (백의 관점, 센티폰)
+    pub eval_after: i32,
+    /// 최선의 수보다 잃은 점수 (0 이상)
+    pub loss: i32,
+    pub class: MoveClass,
+    /// 엔진이 고른 최선의 수. 둔 수가 최선이면 같은 수입니다.
+    pub best_move: ChessMove<'a>,
+    pub best_pv: Vec<ChessMove<'a>>,
+    /// 이 수의 정확도 (0~100). 두기 전후 이길 확률의 차이로 계산합니다.
+    pub accuracy: f64,
+}
+
+/// 한쪽의 요약
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SideReport {
+    pub moves: usize,
+    /// 수마다의 정확도의 평균. 둔 수가 없으면 `None`입니다.
+    pub accuracy: Option<f64>,
+    pub inaccuracies: usize,
+    pub mistakes: usize,
+    pub blunders: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GameReport<'a> {
+    pub moves: Vec<AnnotatedMove<'a>>,
+    pub white: SideReport,
+    pub black: SideReport,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AnnotateError {
+    /// `ply`번째 수를 그 위치에서 둘 수 없습니다.
+    IllegalMove { ply: usize, text: String },
+    /// `ply`번째 수의 표기에 맞는 수가 여럿입니다. (`MoveParseError::Ambiguous`)
+    AmbiguousMove { ply: usize, text: String, candidates: Vec<String> },
+    /// `ply`번째 수 전에 대국이 이미 끝났습니다.
+    GameOver { ply: usize, status: BoardStatus },
+    /// `ply`번째 위치를 탐색하지 못했습니다. (`find_best_move`의 오류 코드)
+    Search { ply: usize, code: usize },
+}
+
+impl fmt::Display for AnnotateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnnotateError::IllegalMove { ply, text } => write!(f, "{}번째 수 {text}는 둘 수 없는 수입니다", ply + 1),
+            AnnotateError::AmbiguousMove { ply, text, candidates } => {
+                write!(f, "{}번째 수 {text}는 어느 수인지 알 수 없습니다: {}", ply + 1, candidates.join(", "))
+            }
+            AnnotateError::GameOver { ply, .. } => write!(f, "{}번째 수 전에 대국이 끝났습니다", ply + 1),
+            AnnotateError::Search { ply, code } => write!(f, "{}번째 위치를 탐색하지 못했습니다 ({code})", ply + 1),
+        }
+    }
+}
+
+/// 차례인 쪽이 이길 확률 (0~100)
+fn win_percent(score: i32) -> f64 {
+    50.0 + 50.0 * (2.0 / (1.0 + (-WIN_RATE_SLOPE * score as f64).exp()) - 1.0)
+}
+
+/// 두기 전후 이길 확률의 차이로 본 수의 정확도 (0~100). 잃은 것이 없으면 거의 100입니다.
+fn move_accuracy(before: i32, after: i32) -> f64 {
+    let drop = (win_percent(before) - win_percent(after)).max(0.0);
+    (103.1668 * (-0.04354 * drop).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+/// `start`에서 좌표 표기(`ChessMove::notation`)로 적은 `moves`를 `Board::make_move_new`로 차례로 두며
+/// 위치마다 `analysis::analyze`로 모든 수를 평가하고, 둔 수의 평가 변화와 분류, 최선의 수,
+/// 그리고 양쪽의 정확도를 계산합니다. 탐색은 위치마다 `config`로 합니다.
+pub fn annotate_game<'a>(
+    start: &Board<'a>,
+    moves: &[&str],
+    config: &SearchConfig,
+) -> Result<GameReport<'a>, AnnotateError> {
+    let mut board = start.clone();
+    let mut annotated = Vec::with_capacity(moves.len());
+    let mut white = SideReport::default();
+    let mut black = SideReport::default();
+    let (mut white_total, mut black_total) = (0.0, 0.0);
+
+    for (ply, text) in moves.iter().enumerate() {
+        if board.is_terminal() {
+            return Err(AnnotateError::GameOver {
+                ply,
+                status: board.status(),
+            });
+        }
+        let illegal = || AnnotateError::IllegalMove {
+            ply,
+            text: text.to_string(),
+        };
+        let mv = board.parse_move(text).map_err(|e| match e {
+            MoveParseError::Illegal => illegal(),
+            MoveParseError::Ambiguous(candidates) => AnnotateError::AmbiguousMove {
+                ply,
+                text: text.to_string(),
+                candidates,
+            },
+        })?;
+        let color = board.side_to_move();
+        let analysis = analyze(&mut board, config.clone()).map_err(|code| AnnotateError::Search { ply, code })?;
+        let best = &analysis.moves[0];
+        let played = analysis.moves.iter().find(|m| m.mv == mv).ok_or_else(illegal)?;
+
+        // 점수는 둔 쪽의 관점이므로 백의 관점으로 바꿔 보여 줍니다.
+        let sign = if color == Color::White { 1 } else { -1 };
+        let accuracy = move_accuracy(best.score, played.score);
+        let (side, total) = match color {
+            Color::White => (&mut white, &mut white_total),
+            Color::Black => (&mut black, &mut black_total),
+        };
+        side.moves += 1;
+        *total += accuracy;
+        match played.class {
+            MoveClass::Inaccuracy => side.inaccuracies += 1,
+            MoveClass::Mistake => side.mistakes += 1,
+            MoveClass::Blunder => side.blunders += 1,
+            MoveClass::Best | MoveClass::Good => {}
+        }
+
+        annotated.push(AnnotatedMove {
+            ply,
+            color,
+            mv: mv.clone(),
+            eval_before: sign * best.score,
+            eval_after: sign * played.score,
+            loss: played.loss,
+            class: played.class,
+            best_move: best.mv.clone(),
+            best_pv: best.pv.clone(),
+            accuracy,
+        });
+        board = board.make_move_new(&mv);
+    }
+
+    white.accuracy = (white.moves > 0).then(|| white_total / white.moves as f64);
+    black.accuracy = (black.moves > 0).then(|| black_total / black.moves as f64);
+    Ok(GameReport {
+        moves: annotated,
+        white,
+        black,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chessembly::ChessemblyCompiled;
+    use crate::engine::search::SearchLimits;
+
+    const CHAMELEON: &str = include_str!("../../chameleon.csm");
+
+    fn config() -> SearchConfig<'static> {
+        SearchConfig {
+            limits: SearchLimits::depth(2),
+            ..SearchConfig::default()
+        }
+    }
+
+    #[test]
+    fn reports_the_ply_of_a_bad_move() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        // 백 킹 b6, 룩 h1, 흑 킹 a8
+        let mate = Board::from_pieces(
+            &compiled,
+            Color::White,
+            &[((1, 2), "king", Color::White), ((7, 7), "rook", Color::White), ((0, 0), "king", Color::Black)],
+        );
+        assert_eq!(
+            annotate_game(&mate, &["h1h2", "a8b8", "h2a1"], &config()).unwrap_err(),
+            AnnotateError::IllegalMove {
+                ply: 2,
+                text: "h2a1".to_string()
+            }
+        );
+        assert_eq!(
+            annotate_game(&mate, &["h1h8", "a8a7"], &config()).unwrap_err(),
+            AnnotateError::GameOver {
+                ply: 1,
+                status: BoardStatus::Checkmate
+            }
+        );
+
+        // 카멜레온이 잡는 칸만 다른 수 넷을 잡는 칸 없이 적으면 어느 수인지 모릅니다.
+        let catches = Board::from_pieces(
+            &compiled,
+            Color::White,
+            &[
+                ((7, 0), "king", Color::White),
+                ((0, 4), "king", Color::Black),
+                ((3, 4), "chameleon", Color::White),
+                ((5, 2), "pawn", Color::Black),
+                ((5, 6), "pawn", Color::Black),
+                ((1, 2), "pawn", Color::Black),
+                ((1, 6), "pawn", Color::Black),
+            ],
+        );
+        match annotate_game(&catches, &["d4d4=mirrored-pawn"], &config()) {
+            Err(AnnotateError::AmbiguousMove { ply: 0, candidates, .. }) => assert_eq!(candidates.len(), 4),
+            other => panic!("{:?}", other.map(|report| report.moves.len())),
+        }
+    }
+
+    #[test]
+    fn scores_are_white_relative_and_accuracy_is_per_side() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        // 백 퀸이 e6의 폰이 지키는 d5의 폰을 잡고, 흑이 다시 잡습니다.
+        let start = Board::from_pieces(
+            &compiled,
+            Color::White,
+            &[
+                ((4, 7), "king", Color::White),
+                ((3, 7), "queen", Color::White),
+                ((4, 0), "king", Color::Black),
+                ((3, 3), "pawn", Color::Black),
+                ((4, 2), "pawn", Color::Black),
+            ],
+        );
+        let report = annotate_game(&start, &["d1d5", "e6d5"], &config()).unwrap();
+        let [white, black] = &report.moves[..] else {
+            panic!("{}수", report.moves.len());
+        };
+
+        assert_eq!((white.color, white.class), (Color::White, MoveClass::Blunder));
+        assert_eq!((black.color, black.class), (Color::Black, MoveClass::Best));
+        // 흑의 점수는 흑의 관점에서 좋지만, 보고서에는 백의 관점으로 뒤집혀 나옵니다.
+        let mut after = start.make_move_new(&white.mv);
+        let analysis = analyze(&mut after, config()).unwrap();
+        let recapture = analysis.moves.iter().find(|m| m.mv == black.mv).unwrap();
+        assert!(recapture.score > 0);
+        assert_eq!(black.eval_after, -recapture.score);
+        assert_eq!(black.eval_before, -analysis.moves[0].score);
+        assert!(white.eval_after < 0 && black.eval_after < 0);
+        assert!(white.eval_before > white.eval_after);
+
+        assert_eq!((report.white.moves, report.white.blunders), (1, 1));
+        assert_eq!((report.black.moves, report.black.blunders), (1, 0));
+        assert_eq!(report.white.accuracy, Some(white.accuracy));
+        assert_eq!(report.black.accuracy, Some(black.accuracy));
+        assert!(white.accuracy < 50.0 && black.accuracy > 99.0, "{} {}", white.accuracy, black.accuracy);
+    }
+}
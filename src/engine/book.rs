@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::game_logic::{GameMove, GameState};
+use super::rng::Rng;
+
+// 바이너리 형식 (모두 리틀 엔디언):
+//   머리: "CSMB", 버전(u8), 스크립트 해시(u64), 항목 수(u32)
+//   항목: 위치 해시(u64), 출발 칸(u8), 도착 칸(u8), 같은 칸 쌍 중 순서(u8), 가중치(u16)
+// 항목은 위치 해시 순으로 정렬되어 있어 이진 탐색으로 찾습니다.
+const MAGIC: &[u8; 4] = b"CSMB";
+/// 2: 스크립트 해시를 `Behavior::canonical`로 적은 글로 계산합니다. 1의 해시는 `Debug` 출력에 묶여 있었습니다.
+const VERSION: u8 = 2;
+const HEADER_SIZE: usize = 4 + 1 + 8 + 4;
+const ENTRY_SIZE: usize = 8 + 1 + 1 + 1 + 2;
+
+/// 북의 수 하나.
+/// 수는 (출발, 도착) 칸과, 그 칸 쌍을 가진 합법적인 수 중 몇 번째인지로 나타냅니다. (변신처럼 칸이 같은 수 구별용)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BookEntry {
+    pub key: u64,
+    pub from: u8,
+    pub to: u8,
+    pub nth: u8,
+    pub weight: u16,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BookError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BookError::BadMagic => write!(f, "오프닝 북 파일이 아닙니다"),
+            BookError::UnsupportedVersion(version) => write!(f, "지원하지 않는 오프닝 북 버전: {version}"),
+            BookError::Truncated => write!(f, "오프닝 북 파일이 잘렸습니다"),
+        }
+    }
+}
+
+/// 위치 해시를 키로 하는 오프닝 북. 한 스크립트(변형)에만 쓸 수 있습니다.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpeningBook {
+    /// 이 북을 만든 스크립트의 `ChessemblyCompiled::script_hash`
+    pub script_hash: u64,
+    entries: Vec<BookEntry>,
+}
+
+impl OpeningBook {
+    pub fn from_bytes(bytes: &[u8]) -> Result<OpeningBook, BookError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(if bytes.starts_with(MAGIC) { BookError::Truncated } else { BookError::BadMagic });
+        }
+        if &bytes[..4] != MAGIC {
+            return Err(BookError::BadMagic);
+        }
+        if bytes[4] != VERSION {
+            return Err(BookError::UnsupportedVersion(bytes[4]));
+        }
+        let script_hash = u64::from_le_bytes(bytes[5..13].try_into().unwrap());
+        let count = u32::from_le_bytes(bytes[13..17].try_into().unwrap()) as usize;
+        let body = &bytes[HEADER_SIZE..];
+        if body.len() < count * ENTRY_SIZE {
+            return Err(BookError::Truncated);
+        }
+
+        let mut entries: Vec<BookEntry> = body
+            .chunks_exact(ENTRY_SIZE)
+            .take(count)
+            .map(|chunk| BookEntry {
+                key: u64::from_le_bytes(chunk[..8].try_into().unwrap()),
+                from: chunk[8],
+                to: chunk[9],
+                nth: chunk[10],
+                weight: u16::from_le_bytes([chunk[11], chunk[12]]),
+            })
+            .collect();
+        // 손으로 만든 파일이 정렬되어 있지 않아도 찾을 수 있게 합니다.
+        entries.sort_by_key(|entry| entry.key);
+        Ok(OpeningBook { script_hash, entries })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.entries.len() * ENTRY_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.script_hash.to_le_bytes());
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for entry in &self.entries {
+            bytes.extend_from_slice(&entry.key.to_le_bytes());
+            bytes.extend_from_slice(&[entry.from, entry.to, entry.nth]);
+            bytes.extend_from_slice(&entry.weight.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 이 위치 해시의 항목들
+    pub fn entries(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+        &self.entries[start..end]
+    }
+
+    /// 이 위치에서 북에 있는 합법적인 수와 그 가중치
+    pub fn book_moves<S: GameState>(&self, state: &mut S) -> Vec<(S::Move, u16)> {
+        let entries = self.entries(state.position_hash());
+        if entries.is_empty() {
+            return Vec::new();
+        }
+        let moves = state.get_legal_moves();
+        entries
+            .iter()
+            .filter_map(|entry| decode_move(&moves, entry).map(|m| (m, entry.weight)))
+            .collect()
+    }
+
+    /// 가중치에 비례한 확률로 북의 수 하나를 고릅니다. 같은 시드와 위치에서는 항상 같은 수입니다.
+    pub fn probe<S: GameState>(&self, state: &mut S, seed: u64) -> Option<S::Move> {
+        let moves = self.book_moves(state);
+        let total: usize = moves.iter().map(|(_, weight)| *weight as usize).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = Rng::new(seed ^ state.position_hash()).below(total);
+        moves.into_iter().find_map(|(m, weight)| {
+            if pick < weight as usize {
+                Some(m)
+            } else {
+                pick -= weight as usize;
+                None
+            }
+        })
+    }
+}
+
+fn encode_move<M: GameMove>(moves: &[M], m: &M) -> Option<(u8, u8, u8)> {
+    let (from, to) = m.squares();
+    let nth = moves
+        .iter()
+        .filter(|other| other.squares() == (from, to))
+        .position(|other| other == m)?;
+    (from < 64 && to < 64 && nth <= u8::MAX as usize).then_some((from as u8, to as u8, nth as u8))
+}
+
+fn decode_move<M: GameMove>(moves: &[M], entry: &BookEntry) -> Option<M> {
+    moves
+        .iter()
+        .filter(|m| m.squares() == (entry.from as usize, entry.to as usize))
+        .nth(entry.nth as usize)
+        .cloned()
+}
+
+/// 대국 기록에서 오프닝 북을 만듭니다.
+/// 수마다 그 수를 둔 쪽이 이긴 대국은 2, 비긴 대국은 1, 진 대국은 0을 더합니다.
+pub struct BookBuilder {
+    script_hash: u64,
+    /// 대국마다 처음 이만큼의 플라이만 북에 넣습니다.
+    max_plies: usize,
+    weights: HashMap<(u64, u8, u8, u8), u32>,
+}
+
+impl BookBuilder {
+    pub fn new(script_hash: u64, max_plies: usize) -> BookBuilder {
+        BookBuilder {
+            script_hash,
+            max_plies,
+            weights: HashMap::new(),
+        }
+    }
+
+    /// `start`에서 `moves`를 둔 대국 하나를 더합니다.
+    /// `result`는 `start`의 차례인 쪽이 본 결과(1, 0.5, 0)이며, 모르면 비긴 것으로 봅니다.
+    /// 합법적이지 않은 수가 나오면 거기서 멈춥니다.
+    pub fn add_game<S: GameState>(&mut self, start: &S, moves: &[S::Move], result: Option<f64>) {
+        let result = result.unwrap_or(0.5);
+        let mut state = start.clone();
+        for (ply, m) in moves.iter().take(self.max_plies).enumerate() {
+            let legal = state.get_legal_moves();
+            let Some((from, to, nth)) = encode_move(&legal, m) else {
+                break;
+            };
+            let mover_result = if ply % 2 == 0 { result } else { 1.0 - result };
+            let weight = (mover_result * 2.0).round() as u32;
+            *self.weights.entry((state.position_hash(), from, to, nth)).or_default() += weight;
+            state = state.make_move(m);
+        }
+    }
+
+    /// 가중치가 `min_weight`보다 작은 수는 빼고 북을 만듭니다.
+    pub fn build(self, min_weight: u32) -> OpeningBook {
+        let max = self.weights.values().copied().max().unwrap_or(0);
+        // 가중치가 u16을 넘으면 비율을 지키며 줄입니다.
+        let scale = if max > u16::MAX as u32 { u16::MAX as f64 / max as f64 } else { 1.0 };
+        let mut entries: Vec<BookEntry> = self
+            .weights
+            .into_iter()
+            .filter(|(_, weight)| *weight >= min_weight.max(1))
+            .map(|((key, from, to, nth), weight)| BookEntry {
+                key,
+                from,
+                to,
+                nth,
+                weight: ((weight as f64 * scale).round() as u16).max(1),
+            })
+            .collect();
+        entries.sort_by_key(|entry| (entry.key, std::cmp::Reverse(entry.weight), entry.from, entry.to, entry.nth));
+        OpeningBook {
+            script_hash: self.script_hash,
+            entries,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chessembly::board::Board;
+    use crate::chessembly::ChessemblyCompiled;
+
+    const CHAMELEON: &str = include_str!("../../chameleon.csm");
+
+    fn sample_book() -> OpeningBook {
+        let mut entries = vec![
+            BookEntry { key: 7, from: 52, to: 36, nth: 0, weight: 3 },
+            BookEntry { key: 3, from: 6, to: 21, nth: 1, weight: 65535 },
+            BookEntry { key: 7, from: 51, to: 35, nth: 0, weight: 1 },
+        ];
+        entries.sort_by_key(|entry| entry.key);
+        OpeningBook { script_hash: 0x0123_4567_89ab_cdef, entries }
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let book = sample_book();
+        let bytes = book.to_bytes();
+        assert_eq!(bytes.len(), HEADER_SIZE + 3 * ENTRY_SIZE);
+        assert_eq!(&bytes[..4], MAGIC);
+        assert_eq!(bytes[4], VERSION);
+        assert_eq!(OpeningBook::from_bytes(&bytes), Ok(book.clone()));
+        assert_eq!(book.entries(7).len(), 2);
+        assert_eq!(book.entries(5), &[]);
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let bytes = sample_book().to_bytes();
+        assert_eq!(OpeningBook::from_bytes(b"XXXX"), Err(BookError::BadMagic));
+        assert_eq!(OpeningBook::from_bytes(&bytes[..HEADER_SIZE - 1]), Err(BookError::Truncated));
+        assert_eq!(OpeningBook::from_bytes(&bytes[..bytes.len() - 1]), Err(BookError::Truncated));
+        let mut old = bytes.clone();
+        old[4] = 1;
+        assert_eq!(OpeningBook::from_bytes(&old), Err(BookError::UnsupportedVersion(1)));
+    }
+
+    #[test]
+    fn built_book_plays_back_its_games() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let start = Board::new(&compiled);
+        let mut board = start.clone();
+        let e4 = board.parse_move("e2e4").unwrap();
+        let d4 = board.parse_move("d2d4").unwrap();
+
+        let mut builder = BookBuilder::new(compiled.script_hash(), 4);
+        builder.add_game(&start, std::slice::from_ref(&e4), Some(1.0));
+        builder.add_game(&start, std::slice::from_ref(&d4), Some(0.0));
+        let book = builder.build(1);
+        // 진 대국의 수는 가중치가 0이라 빠집니다.
+        assert_eq!(book.book_moves(&mut board), vec![(e4.clone(), 2)]);
+        assert_eq!(book.probe(&mut board, 42), Some(e4.clone()));
+
+        let mut after = board.make_move_new(&e4);
+        assert_eq!(book.probe(&mut after, 42), None);
+    }
+
+    #[test]
+    fn probe_is_deterministic_for_a_seed() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let start = Board::new(&compiled);
+        let mut board = start.clone();
+        let mut builder = BookBuilder::new(compiled.script_hash(), 1);
+        for text in ["e2e4", "d2d4", "g1f3"] {
+            let m = board.parse_move(text).unwrap();
+            builder.add_game(&start, &[m], Some(0.5));
+        }
+        let book = OpeningBook::from_bytes(&builder.build(1).to_bytes()).unwrap();
+        for seed in 0..8 {
+            let first = book.probe(&mut board, seed);
+            assert!(first.is_some());
+            assert_eq!(book.probe(&mut board, seed), first);
+        }
+    }
+}
@@ -0,0 +1,677 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chessembly::board::Board;
+use crate::chessembly::{Color, MoveGen, Position};
+
+use super::game_logic::GameState;
+use super::tablebase::Tablebases;
+use super::values::piece_value;
+
+/// 종료되지 않은 위치를 현재 차례인 쪽의 관점에서 센티폰 단위로 평가합니다.
+/// 체크메이트나 무승부 같은 종료 상태는 `GameState::evaluate`가 처리합니다.
+pub trait Evaluator<S> {
+    fn evaluate(&self, state: &mut S) -> i32;
+}
+
+impl<S, E: Evaluator<S>> Evaluator<S> for &E {
+    fn evaluate(&self, state: &mut S) -> i32 {
+        (**self).evaluate(state)
+    }
+}
+
+/// 미들게임/엔드게임 점수 쌍. 남은 기물에 따라 둘 사이를 보간합니다.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Phased {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+const fn phased(mg: i32, eg: i32) -> Phased {
+    Phased { mg, eg }
+}
+
+/// 기물이 모두 있을 때의 게임 단계 값. 나이트/비숍 1, 룩 2, 퀸 4.
+pub const TOTAL_PHASE: i32 = 24;
+
+/// 게임 단계(`0..=TOTAL_PHASE`)에 따라 미들게임과 엔드게임 점수를 보간합니다.
+pub fn taper(mg: i32, eg: i32, phase: i32) -> i32 {
+    (mg * phase + eg * (TOTAL_PHASE - phase)) / TOTAL_PHASE
+}
+
+/// 표준 기물이 아니면 가치로 단계 값을 정합니다.
+fn piece_phase(piece: &str, value: i32) -> i32 {
+    match piece {
+        "pawn" | "king" => 0,
+        "knight" | "bishop" => 1,
+        "rook" => 2,
+        "queen" => 4,
+        _ => (value / 250).clamp(1, 4),
+    }
+}
+
+// 가중치 번호. `EvalWeights::for_each_param`이 도는 순서와 같습니다.
+// 표 8개(폰, 나이트, 비숍, 룩, 퀸, 킹 미들게임, 킹 엔드게임, 그 밖의 기물)가 64칸씩 먼저 옵니다.
+const PAWN_TABLE: usize = 0;
+const KNIGHT_TABLE: usize = 1;
+const BISHOP_TABLE: usize = 2;
+const ROOK_TABLE: usize = 3;
+const QUEEN_TABLE: usize = 4;
+const KING_MG_TABLE: usize = 5;
+const KING_EG_TABLE: usize = 6;
+const OTHER_TABLE: usize = 7;
+const MOBILITY: usize = 8 * 64;
+const DOUBLED_PAWN: usize = MOBILITY + 2;
+const ISOLATED_PAWN: usize = DOUBLED_PAWN + 2;
+const PASSED_PAWN: usize = ISOLATED_PAWN + 2;
+const PAWN_SHIELD: usize = PASSED_PAWN + 16;
+const KING_ZONE_ATTACK: usize = PAWN_SHIELD + 1;
+/// 조절할 수 있는 가중치의 개수 (기물 가치 제외)
+pub const PARAM_COUNT: usize = KING_ZONE_ATTACK + 1;
+
+#[inline]
+fn table_param(table: usize, row: usize, col: usize) -> usize {
+    table * 64 + row * 8 + col
+}
+
+/// 위치 평가의 가중치. JSON 가중치 파일로 저장하고 읽을 수 있습니다. (빠진 항목은 기본값)
+/// 기물-칸 표는 백의 관점으로, 첫 줄이 8랭크(흑의 첫 줄)입니다. 흑은 위아래를 뒤집어 씁니다.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EvalWeights {
+    /// 기물 가치 (센티폰). 없는 기물은 스크립트의 값(`values::piece_value`)을 씁니다.
+    pub piece_values: BTreeMap<String, i32>,
+    pub pawn_table: [[i32; 8]; 8],
+    pub knight_table: [[i32; 8]; 8],
+    pub bishop_table: [[i32; 8]; 8],
+    pub rook_table: [[i32; 8]; 8],
+    pub queen_table: [[i32; 8]; 8],
+    pub king_mg_table: [[i32; 8]; 8],
+    pub king_eg_table: [[i32; 8]; 8],
+    /// 스크립트가 새로 정의한 기물에 쓰는 표 (중앙일수록 좋음)
+    pub other_table: [[i32; 8]; 8],
+    /// 폰과 킹을 뺀 기물의 수 하나당 점수
+    pub mobility: Phased,
+    /// 같은 줄에 겹친 폰 하나당 점수
+    pub doubled_pawn: Phased,
+    /// 옆 줄에 같은 편 폰이 없는 폰 하나당 점수
+    pub isolated_pawn: Phased,
+    /// 막을 상대 폰이 없는 폰의 점수. 자기 진영 첫 줄로부터 몇 칸 나아갔는지로 찾습니다.
+    pub passed_pawn: [Phased; 8],
+    /// 킹 바로 앞 세 칸에 있는 같은 편 폰 하나당 점수 (미들게임)
+    pub pawn_shield: i32,
+    /// 상대가 공격하는 킹 주변 칸 하나당 점수 (미들게임)
+    pub king_zone_attack: i32,
+}
+
+impl EvalWeights {
+    #[rustfmt::skip]
+    pub const DEFAULT: EvalWeights = EvalWeights {
+        piece_values: BTreeMap::new(),
+        pawn_table: [
+            [  0,   0,   0,   0,   0,   0,   0,   0],
+            [ 50,  50,  50,  50,  50,  50,  50,  50],
+            [ 10,  10,  20,  30,  30,  20,  10,  10],
+            [  5,   5,  10,  25,  25,  10,   5,   5],
+            [  0,   0,   0,  20,  20,   0,   0,   0],
+            [  5,  -5, -10,   0,   0, -10,  -5,   5],
+            [  5,  10,  10, -20, -20,  10,  10,   5],
+            [  0,   0,   0,   0,   0,   0,   0,   0],
+        ],
+        knight_table: [
+            [-50, -40, -30, -30, -30, -30, -40, -50],
+            [-40, -20,   0,   0,   0,   0, -20, -40],
+            [-30,   0,  10,  15,  15,  10,   0, -30],
+            [-30,   5,  15,  20,  20,  15,   5, -30],
+            [-30,   0,  15,  20,  20,  15,   0, -30],
+            [-30,   5,  10,  15,  15,  10,   5, -30],
+            [-40, -20,   0,   5,   5,   0, -20, -40],
+            [-50, -40, -30, -30, -30, -30, -40, -50],
+        ],
+        bishop_table: [
+            [-20, -10, -10, -10, -10, -10, -10, -20],
+            [-10,   0,   0,   0,   0,   0,   0, -10],
+            [-10,   0,   5,  10,  10,   5,   0, -10],
+            [-10,   5,   5,  10,  10,   5,   5, -10],
+            [-10,   0,  10,  10,  10,  10,   0, -10],
+            [-10,  10,  10,  10,  10,  10,  10, -10],
+            [-10,   5,   0,   0,   0,   0,   5, -10],
+            [-20, -10, -10, -10, -10, -10, -10, -20],
+        ],
+        rook_table: [
+            [  0,   0,   0,   0,   0,   0,   0,   0],
+            [  5,  10,  10,  10,  10,  10,  10,   5],
+            [ -5,   0,   0,   0,   0,   0,   0,  -5],
+            [ -5,   0,   0,   0,   0,   0,   0,  -5],
+            [ -5,   0,   0,   0,   0,   0,   0,  -5],
+            [ -5,   0,   0,   0,   0,   0,   0,  -5],
+            [ -5,   0,   0,   0,   0,   0,   0,  -5],
+            [  0,   0,   0,   5,   5,   0,   0,   0],
+        ],
+        queen_table: [
+            [-20, -10, -10,  -5,  -5, -10, -10, -20],
+            [-10,   0,   0,   0,   0,   0,   0, -10],
+            [-10,   0,   5,   5,   5,   5,   0, -10],
+            [ -5,   0,   5,   5,   5,   5,   0,  -5],
+            [  0,   0,   5,   5,   5,   5,   0,  -5],
+            [-10,   5,   5,   5,   5,   5,   0, -10],
+            [-10,   0,   5,   0,   0,   0,   0, -10],
+            [-20, -10, -10,  -5,  -5, -10, -10, -20],
+        ],
+        king_mg_table: [
+            [-30, -40, -40, -50, -50, -40, -40, -30],
+            [-30, -40, -40, -50, -50, -40, -40, -30],
+            [-30, -40, -40, -50, -50, -40, -40, -30],
+            [-30, -40, -40, -50, -50, -40, -40, -30],
+            [-20, -30, -30, -40, -40, -30, -30, -20],
+            [-10, -20, -20, -20, -20, -20, -20, -10],
+            [ 20,  20,   0,   0,   0,   0,  20,  20],
+            [ 20,  30,  10,   0,   0,  10,  30,  20],
+        ],
+        king_eg_table: [
+            [-50, -40, -30, -20, -20, -30, -40, -50],
+            [-30, -20, -10,   0,   0, -10, -20, -30],
+            [-30, -10,  20,  30,  30,  20, -10, -30],
+            [-30, -10,  30,  40,  40,  30, -10, -30],
+            [-30, -10,  30,  40,  40,  30, -10, -30],
+            [-30, -10,  20,  30,  30,  20, -10, -30],
+            [-30, -30,   0,   0,   0,   0, -30, -30],
+            [-50, -30, -30, -30, -30, -30, -30, -50],
+        ],
+        other_table: [
+            [-20, -10, -10, -10, -10, -10, -10, -20],
+            [-10,   0,   0,   0,   0,   0,   0, -10],
+            [-10,   0,   5,   5,   5,   5,   0, -10],
+            [-10,   0,   5,  10,  10,   5,   0, -10],
+            [-10,   0,   5,  10,  10,   5,   0, -10],
+            [-10,   0,   5,   5,   5,   5,   0, -10],
+            [-10,   0,   0,   0,   0,   0,   0, -10],
+            [-20, -10, -10, -10, -10, -10, -10, -20],
+        ],
+        mobility: phased(4, 3),
+        doubled_pawn: phased(-10, -20),
+        isolated_pawn: phased(-10, -15),
+        passed_pawn: [
+            phased(0, 0), phased(5, 10), phased(5, 15), phased(10, 25),
+            phased(20, 45), phased(35, 75), phased(60, 120), phased(0, 0),
+        ],
+        pawn_shield: 10,
+        king_zone_attack: -8,
+    };
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        EvalWeights::DEFAULT
+    }
+}
+
+impl EvalWeights {
+    /// 기물 가치를 뺀 모든 가중치를 번호 순서대로 방문합니다.
+    pub fn for_each_param(&mut self, mut f: impl FnMut(&mut i32)) {
+        for table in [
+            &mut self.pawn_table,
+            &mut self.knight_table,
+            &mut self.bishop_table,
+            &mut self.rook_table,
+            &mut self.queen_table,
+            &mut self.king_mg_table,
+            &mut self.king_eg_table,
+            &mut self.other_table,
+        ] {
+            table.iter_mut().flatten().for_each(&mut f);
+        }
+        for phased in [&mut self.mobility, &mut self.doubled_pawn, &mut self.isolated_pawn]
+            .into_iter()
+            .chain(self.passed_pawn.iter_mut())
+        {
+            f(&mut phased.mg);
+            f(&mut phased.eg);
+        }
+        f(&mut self.pawn_shield);
+        f(&mut self.king_zone_attack);
+    }
+
+    /// 가중치를 번호 순서의 배열로 꺼냅니다. (길이 `PARAM_COUNT`)
+    pub fn params(&self) -> Vec<i32> {
+        let mut params = Vec::with_capacity(PARAM_COUNT);
+        self.clone().for_each_param(|value| params.push(*value));
+        params
+    }
+
+    /// `params`로 꺼낸 배열을 다시 넣습니다.
+    pub fn set_params(&mut self, params: &[i32]) {
+        let mut params = params.iter();
+        self.for_each_param(|value| {
+            if let Some(param) = params.next() {
+                *value = *param;
+            }
+        });
+    }
+}
+
+/// 평가 항을 받는 쪽. 실제 점수 계산과 튜너의 특징 추출이 같은 평가 코드를 씁니다.
+/// 모든 항은 백의 관점입니다.
+pub trait EvalSink {
+    /// `param`번 가중치(현재 값 `value`)를 미들게임 점수에 `mg`번, 엔드게임 점수에 `eg`번 더합니다.
+    fn term(&mut self, param: usize, value: i32, mg: i32, eg: i32);
+
+    /// 기물 가치 `value`를 미들게임과 엔드게임 점수에 `count`번 더합니다.
+    fn material(&mut self, piece: &str, value: i32, count: i32);
+
+    fn phased(&mut self, param: usize, value: Phased, count: i32) {
+        self.term(param, value.mg, count, 0);
+        self.term(param + 1, value.eg, 0, count);
+    }
+}
+
+#[derive(Default)]
+struct ScoreSink {
+    mg: i32,
+    eg: i32,
+}
+
+impl EvalSink for ScoreSink {
+    #[inline]
+    fn term(&mut self, _param: usize, value: i32, mg: i32, eg: i32) {
+        self.mg += value * mg;
+        self.eg += value * eg;
+    }
+
+    #[inline]
+    fn material(&mut self, _piece: &str, value: i32, count: i32) {
+        self.mg += value * count;
+        self.eg += value * count;
+    }
+}
+
+/// 한 위치의 평가를 가중치에 대한 일차식으로 펼친 것. (튜너용)
+/// 점수는 `taper(Σ 가중치 × mg 계수, Σ 가중치 × eg 계수, phase)`입니다.
+#[derive(Clone, Debug, Default)]
+pub struct EvalTrace {
+    pub phase: i32,
+    /// (가중치 번호, 미들게임 계수, 엔드게임 계수)
+    pub terms: Vec<(usize, i32, i32)>,
+    /// (기물, 개수 차이)
+    pub material: Vec<(String, i32)>,
+}
+
+impl EvalSink for EvalTrace {
+    fn term(&mut self, param: usize, _value: i32, mg: i32, eg: i32) {
+        if mg != 0 || eg != 0 {
+            self.terms.push((param, mg, eg));
+        }
+    }
+
+    fn material(&mut self, piece: &str, _value: i32, count: i32) {
+        match self.material.iter_mut().find(|(name, _)| name == piece) {
+            Some((_, total)) => *total += count,
+            None => self.material.push((piece.to_string(), count)),
+        }
+    }
+}
+
+/// 기물 가치만 세는 평가 (예전 평가 함수)
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MaterialEvaluator;
+
+impl<'a> Evaluator<Board<'a>> for MaterialEvaluator {
+    fn evaluate(&self, board: &mut Board<'a>) -> i32 {
+        let mut score = 0;
+        for y in 0..8u8 {
+            for x in 0..8u8 {
+                if let (Some(piece), Some(color)) = (board.piece_on(&(x, y)), board.color_on(&(x, y))) {
+                    score += sign(color) * piece_value(board, piece);
+                }
+            }
+        }
+        score * sign(board.side_to_move())
+    }
+}
+
+/// 기물-칸 표, 기동성, 폰 구조, 킹 안전도를 미들게임/엔드게임 가중치로 보간하는 평가
+#[derive(Clone, Debug, Default)]
+pub struct PositionalEvaluator {
+    pub weights: EvalWeights,
+}
+
+/// `Board`의 `GameState::evaluate`가 쓰는 기본 평가
+pub static DEFAULT_EVALUATOR: PositionalEvaluator = PositionalEvaluator {
+    weights: EvalWeights::DEFAULT,
+};
+
+#[inline]
+fn sign(color: Color) -> i32 {
+    if color == Color::White {
+        1
+    } else {
+        -1
+    }
+}
+
+#[inline]
+fn side(color: Color) -> usize {
+    if color == Color::White {
+        0
+    } else {
+        1
+    }
+}
+
+/// 백의 관점 표에서 읽을 (줄, 칸). 흑은 위아래를 뒤집습니다.
+#[inline]
+fn relative(color: Color, (x, y): Position) -> (usize, usize) {
+    let row = if color == Color::White { y } else { 7 - y };
+    (row as usize, x as usize)
+}
+
+impl PositionalEvaluator {
+    /// JSON 가중치 파일에서 읽습니다.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        Ok(PositionalEvaluator {
+            weights: serde_json::from_str(json)?,
+        })
+    }
+
+    /// 백의 관점에서 평가 항을 `sink`에 넘기고, 게임 단계(`0..=TOTAL_PHASE`)를 반환합니다.
+    pub fn accumulate(&self, board: &mut Board, sink: &mut impl EvalSink) -> i32 {
+        let w = &self.weights;
+        let mut phase = 0;
+        let mut pawns: [Vec<Position>; 2] = [Vec::new(), Vec::new()];
+        let mut kings: [Vec<Position>; 2] = [Vec::new(), Vec::new()];
+
+        // 1. 기물 가치와 기물-칸 표
+        for y in 0..8u8 {
+            for x in 0..8u8 {
+                let (Some(piece), Some(color)) = (board.piece_on(&(x, y)), board.color_on(&(x, y))) else {
+                    continue;
+                };
+                let s = sign(color);
+                let (row, col) = relative(color, (x, y));
+                let table = match piece {
+                    "pawn" => Some((PAWN_TABLE, &w.pawn_table)),
+                    "knight" => Some((KNIGHT_TABLE, &w.knight_table)),
+                    "bishop" => Some((BISHOP_TABLE, &w.bishop_table)),
+                    "rook" => Some((ROOK_TABLE, &w.rook_table)),
+                    "queen" => Some((QUEEN_TABLE, &w.queen_table)),
+                    "king" => None,
+                    _ => Some((OTHER_TABLE, &w.other_table)),
+                };
+                match table {
+                    Some((index, table)) => sink.term(table_param(index, row, col), table[row][col], s, s),
+                    None => {
+                        sink.term(table_param(KING_MG_TABLE, row, col), w.king_mg_table[row][col], s, 0);
+                        sink.term(table_param(KING_EG_TABLE, row, col), w.king_eg_table[row][col], 0, s);
+                    }
+                }
+
+                let value = w
+                    .piece_values
+                    .get(piece)
+                    .copied()
+                    .unwrap_or_else(|| piece_value(board, piece));
+                sink.material(piece, value, s);
+                phase += piece_phase(piece, value);
+
+                match piece {
+                    "pawn" => pawns[side(color)].push((x, y)),
+                    "king" => kings[side(color)].push((x, y)),
+                    _ => {}
+                }
+            }
+        }
+
+        // 2. 기동성: 폰과 킹을 뺀 기물의 (유사) 합법 수 개수
+        let mut attacked = [[false; 64]; 2];
+        for color in [Color::White, Color::Black] {
+            let moves = MoveGen::get_all_moves(board, color, false);
+            let mobility = moves
+                .iter()
+                .filter(|m| !matches!(board.piece_on(&m.from), Some("pawn") | Some("king")))
+                .count() as i32;
+            sink.phased(MOBILITY, w.mobility, sign(color) * mobility);
+            for m in &moves {
+                attacked[side(color)][m.take.1 as usize * 8 + m.take.0 as usize] = true;
+            }
+        }
+
+        for color in [Color::White, Color::Black] {
+            let s = sign(color);
+            let own = &pawns[side(color)];
+            let enemy = &pawns[side(color.invert())];
+            // 흑은 y가 커지는 쪽으로, 백은 작아지는 쪽으로 나아갑니다.
+            let forward: i32 = if color == Color::White { -1 } else { 1 };
+
+            // 3. 폰 구조
+            let mut files = [0; 8];
+            for &(x, _) in own {
+                files[x as usize] += 1;
+            }
+            for &(x, y) in own {
+                let file = x as usize;
+                let neighbours = |f: usize| f < 8 && files[f] > 0;
+                if !neighbours(file.wrapping_sub(1)) && !neighbours(file + 1) {
+                    sink.phased(ISOLATED_PAWN, w.isolated_pawn, s);
+                }
+
+                let blocked = enemy.iter().any(|&(ex, ey)| {
+                    (ex as i32 - x as i32).abs() <= 1 && (ey as i32 - y as i32) * forward > 0
+                });
+                if !blocked {
+                    let (row, _) = relative(color, (x, y));
+                    let rank = 7 - row;
+                    sink.phased(PASSED_PAWN + rank * 2, w.passed_pawn[rank], s);
+                }
+            }
+            for count in files {
+                if count > 1 {
+                    sink.phased(DOUBLED_PAWN, w.doubled_pawn, s * (count - 1));
+                }
+            }
+
+            // 4. 킹 안전도: 앞을 막아 주는 폰과 상대가 노리는 주변 칸 (미들게임에만)
+            for &(kx, ky) in &kings[side(color)] {
+                let mut shield = 0;
+                let mut zone_attacks = 0;
+                for dx in -1..=1 {
+                    for dy in -1..=1 {
+                        let (x, y) = (kx as i32 + dx, ky as i32 + dy);
+                        if !(0..8).contains(&x) || !(0..8).contains(&y) {
+                            continue;
+                        }
+                        if dy == forward && own.contains(&(x as u8, y as u8)) {
+                            shield += 1;
+                        }
+                        if attacked[side(color.invert())][(y * 8 + x) as usize] {
+                            zone_attacks += 1;
+                        }
+                    }
+                }
+                sink.term(PAWN_SHIELD, w.pawn_shield, s * shield, 0);
+                sink.term(KING_ZONE_ATTACK, w.king_zone_attack, s * zone_attacks, 0);
+            }
+        }
+
+        phase.min(TOTAL_PHASE)
+    }
+
+    /// 튜너가 쓰는, 백의 관점에서 펼친 평가
+    pub fn trace(&self, board: &mut Board) -> EvalTrace {
+        let mut trace = EvalTrace::default();
+        trace.phase = self.accumulate(board, &mut trace);
+        trace
+    }
+}
+
+impl<'a> Evaluator<Board<'a>> for PositionalEvaluator {
+    fn evaluate(&self, board: &mut Board<'a>) -> i32 {
+        let mut score = ScoreSink::default();
+        // 5. 남은 기물로 미들게임과 엔드게임 점수를 보간합니다.
+        let phase = self.accumulate(board, &mut score);
+        taper(score.mg, score.eg, phase) * sign(board.side_to_move())
+    }
+}
+
+/// 다른 평가 함수로 탐색하고 싶을 때 상태를 감싸는 어댑터.
+/// 종료 상태의 점수는 원래 상태의 `evaluate`를 그대로 씁니다.
+#[derive(Clone, Debug)]
+pub struct Evaluated<S, E> {
+    pub state: S,
+    pub evaluator: E,
+}
+
+impl<S: GameState, E: Evaluator<S> + Clone> GameState for Evaluated<S, E> {
+    type Move = S::Move;
+
+    fn get_legal_moves(&mut self) -> Vec<Self::Move> {
+        self.state.get_legal_moves()
+    }
+
+    fn make_move(&self, m: &Self::Move) -> Self {
+        Evaluated {
+            state: self.state.make_move(m),
+            evaluator: self.evaluator.clone(),
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.state.is_terminal()
+    }
+
+    fn evaluate(&mut self) -> i32 {
+        if self.state.is_terminal() {
+            self.state.evaluate()
+        } else {
+            self.evaluator.evaluate(&mut self.state)
+        }
+    }
+
+    fn position_hash(&self) -> u64 {
+        self.state.position_hash()
+    }
+
+    fn score_move(&self, m: &Self::Move) -> i32 {
+        self.state.score_move(m)
+    }
+
+    fn noisy_gain(&self, m: &Self::Move) -> Option<i32> {
+        self.state.noisy_gain(m)
+    }
+
+    fn is_check(&mut self) -> bool {
+        self.state.is_check()
+    }
+
+    fn make_null_move(&self) -> Self {
+        Evaluated {
+            state: self.state.make_null_move(),
+            evaluator: self.evaluator.clone(),
+        }
+    }
+
+    fn has_non_pawn_material(&self) -> bool {
+        self.state.has_non_pawn_material()
+    }
+
+    fn tablebase_score(&self, tables: &Tablebases) -> Option<i32> {
+        self.state.tablebase_score(tables)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chessembly::{ChessemblyCompiled, Piece, PieceSpan};
+    use crate::engine::rng::Rng;
+
+    /// 위아래를 뒤집고 색과 차례를 바꾼 위치
+    fn mirrored<'a>(board: &Board<'a>) -> Board<'a> {
+        let mut mirror = board.clone();
+        for y in 0..8 {
+            for x in 0..8 {
+                mirror.board[7 - y][x] = match &board.board[y][x] {
+                    PieceSpan::Piece(piece) => PieceSpan::Piece(Piece {
+                        piece_type: piece.piece_type,
+                        color: piece.color.invert(),
+                    }),
+                    PieceSpan::Empty => PieceSpan::Empty,
+                };
+            }
+        }
+        mirror.board_state.white = board.board_state.black.clone();
+        mirror.board_state.black = board.board_state.white.clone();
+        for state in [&mut mirror.board_state.white, &mut mirror.board_state.black] {
+            for (_, y) in &mut state.enpassant {
+                *y = 7 - *y;
+            }
+        }
+        mirror.turn = board.turn.invert();
+        mirror.dp.clear();
+        mirror
+    }
+
+    #[test]
+    fn evaluation_is_colour_symmetric() {
+        let compiled = ChessemblyCompiled::from_script(include_str!("../../chameleon.csm")).unwrap();
+        let mut board = Board::new(&compiled);
+        let mut rng = Rng::new(3);
+        for ply in 0..24 {
+            let mut mirror = mirrored(&board);
+            assert_eq!(
+                DEFAULT_EVALUATOR.evaluate(&mut board),
+                DEFAULT_EVALUATOR.evaluate(&mut mirror),
+                "{ply}플라이"
+            );
+            assert_eq!(MaterialEvaluator.evaluate(&mut board), MaterialEvaluator.evaluate(&mut mirror));
+
+            let moves = board.get_legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            let m = moves[rng.below(moves.len())].clone();
+            board = board.make_move(&m);
+        }
+    }
+
+    #[test]
+    fn taper_interpolates_between_phases() {
+        assert_eq!(taper(100, -20, TOTAL_PHASE), 100);
+        assert_eq!(taper(100, -20, 0), -20);
+        assert_eq!(taper(100, -20, TOTAL_PHASE / 2), 40);
+    }
+
+    #[test]
+    fn search_uses_the_configured_weights() {
+        use crate::engine::search::{find_best_move, SearchConfig, SearchLimits};
+
+        let compiled = ChessemblyCompiled::from_script(include_str!("../../chameleon.csm")).unwrap();
+        let board = Board::new(&compiled);
+        // 기동성을 크게 쳐 주는 가중치로는 1. e4 뒤의 평가가 달라집니다.
+        let mobile = PositionalEvaluator::from_json(r#"{"mobility": {"mg": 40, "eg": 40}}"#).unwrap();
+        let e4 = board.clone().get_legal_moves().into_iter().find(|m| m.notation() == "e2e4").unwrap();
+        let mut after = board.make_move(&e4);
+        assert_ne!(after.evaluate_with(&mobile), after.evaluate());
+        assert_eq!(after.evaluate_with(&mobile), mobile.evaluate(&mut after));
+
+        let search = |evaluator| {
+            let config = SearchConfig {
+                limits: SearchLimits::depth(1),
+                evaluator,
+                ..SearchConfig::default()
+            };
+            find_best_move(&mut board.clone(), &config).unwrap().score
+        };
+        assert_ne!(search(Some(&mobile)), search(None));
+    }
+
+    #[test]
+    fn params_round_trip() {
+        let mut weights = EvalWeights::default();
+        let mut params = weights.params();
+        assert_eq!(params.len(), PARAM_COUNT);
+        params[0] += 7;
+        weights.set_params(&params);
+        assert_eq!(weights.params(), params);
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::game_logic::{GameState, MATE_SCORE};
+
+/// 메이트 탐색 설정
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MateConfig {
+    /// 공격하는 쪽은 체크하는 수만 봅니다. 훨씬 빠르지만, 메이트가 없다는 결과는
+    /// "체크만으로는 메이트할 수 없다"는 뜻이 됩니다.
+    pub checks_only: bool,
+    /// 이만큼 수를 두어 보고도 끝나지 않으면 `MateSearch::Aborted`를 반환합니다.
+    pub max_nodes: Option<u64>,
+}
+
+/// 공격하는 쪽의 수 하나와, 그 뒤 상대의 모든 응수
+#[derive(Clone, Debug, Serialize)]
+pub struct MateLine<M> {
+    #[serde(rename = "move")]
+    pub mv: M,
+    /// 이 수를 포함해 몇 수 만에 메이트하는지 (가장 짧은 값)
+    pub mate_in: u8,
+    /// 상대의 모든 합법적인 응수. 이 수로 바로 메이트면 비어 있습니다.
+    pub replies: Vec<MateDefense<M>>,
+}
+
+/// 상대의 응수 하나와, 남은 수 안에 메이트하는 공격하는 쪽의 모든 수
+#[derive(Clone, Debug, Serialize)]
+pub struct MateDefense<M> {
+    #[serde(rename = "move")]
+    pub mv: M,
+    /// 둘 이상이면 이 응수 뒤에 해답이 여러 개(dual)입니다. 짧은 메이트가 앞에 옵니다.
+    pub answers: Vec<MateLine<M>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MateSolution<M> {
+    /// 가장 짧은 강제 메이트 (수)
+    pub mate_in: u8,
+    /// 가장 짧은 메이트의 첫 수가 하나뿐이고 그 해답 트리에 dual이 없는지. 퍼즐의 해답이 유일한지 확인할 때 씁니다.
+    /// 더 긴 메이트의 첫 수는 보지 않습니다.
+    pub unique: bool,
+    /// 가장 짧은 메이트의 해답 트리에서 해답이 여러 개(dual)인 상대의 응수 수
+    pub duals: usize,
+    /// 주어진 수 안에 메이트하는 모든 첫 수(key). 짧은 메이트가 앞에 옵니다.
+    pub keys: Vec<MateLine<M>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "result", rename_all = "kebab-case")]
+pub enum MateSearch<M> {
+    /// 차례인 쪽이 주어진 수 안에 강제로 메이트합니다.
+    Mate(MateSolution<M>),
+    /// 주어진 수 안에 강제 메이트가 없습니다.
+    NoMate,
+    /// 노드 한도에 걸려 증명도 반증도 하지 못했습니다.
+    Aborted,
+}
+
+struct Solver<'l> {
+    config: &'l MateConfig,
+    nodes: u64,
+    aborted: bool,
+    /// 위치별로 증명한 가장 짧은 메이트 (공격하는 쪽 차례)
+    proven: HashMap<u64, u8>,
+    /// 위치별로 메이트가 없다고 확인한 가장 긴 수
+    refuted: HashMap<u64, u8>,
+}
+
+impl<'l> Solver<'l> {
+    fn play<S: GameState>(&mut self, state: &S, m: &S::Move) -> S {
+        self.nodes += 1;
+        if self.config.max_nodes.is_some_and(|max| self.nodes > max) {
+            self.aborted = true;
+        }
+        state.make_move(m)
+    }
+
+    /// 공격하는 쪽의 수와 그 뒤의 위치. 체크하는 수를 앞에 둡니다.
+    fn attacks<S: GameState>(&mut self, state: &mut S) -> Vec<(S::Move, S)> {
+        let mut children = Vec::new();
+        for m in state.get_legal_moves() {
+            let mut child = self.play(state, &m);
+            let check = child.is_check();
+            if check || !self.config.checks_only {
+                children.push((m, child, check));
+            }
+        }
+        children.sort_by_key(|(_, _, check)| !check);
+        children.into_iter().map(|(m, child, _)| (m, child)).collect()
+    }
+
+    fn is_checkmated<S: GameState>(state: &mut S) -> bool {
+        state.is_terminal() && state.evaluate() == -MATE_SCORE
+    }
+
+    /// 공격하는 쪽 차례에서 `n`수 안에 메이트할 수 있는지
+    fn mates_within<S: GameState>(&mut self, state: &mut S, n: u8) -> bool {
+        if n == 0 || state.is_terminal() || self.aborted {
+            return false;
+        }
+        let key = state.position_hash();
+        if self.proven.get(&key).is_some_and(|&k| k <= n) {
+            return true;
+        }
+        if self.refuted.get(&key).is_some_and(|&k| k >= n) {
+            return false;
+        }
+
+        let mut found = false;
+        for (_, mut child) in self.attacks(state) {
+            if self.defender_loses(&mut child, n - 1) {
+                found = true;
+                break;
+            }
+        }
+        if self.aborted {
+            return false;
+        }
+        if found {
+            let entry = self.proven.entry(key).or_insert(n);
+            *entry = (*entry).min(n);
+        } else {
+            let entry = self.refuted.entry(key).or_insert(n);
+            *entry = (*entry).max(n);
+        }
+        found
+    }
+
+    /// 방어하는 쪽 차례에서, 이미 메이트 당했거나 어떻게 두어도 공격하는 쪽이 `n`수 안에 메이트하는지
+    fn defender_loses<S: GameState>(&mut self, state: &mut S, n: u8) -> bool {
+        if state.is_terminal() {
+            return Self::is_checkmated(state);
+        }
+        if n == 0 {
+            return false;
+        }
+        for m in state.get_legal_moves() {
+            let mut child = self.play(state, &m);
+            if !self.mates_within(&mut child, n) {
+                return false;
+            }
+        }
+        !self.aborted
+    }
+
+    /// 공격하는 쪽이 수를 둔 뒤의 위치에서, 그 수를 포함한 가장 짧은 메이트 (`max` 이내)
+    fn shortest<S: GameState>(&mut self, after: &mut S, max: u8) -> Option<u8> {
+        (1..=max).find(|&k| self.defender_loses(after, k - 1))
+    }
+
+    fn line<S: GameState>(&mut self, m: S::Move, mut after: S, mate_in: u8) -> MateLine<S::Move> {
+        let mut replies = Vec::new();
+        if !after.is_terminal() {
+            for reply in after.get_legal_moves() {
+                let mut position = self.play(&after, &reply);
+                let answers = self.lines(&mut position, mate_in - 1);
+                replies.push(MateDefense { mv: reply, answers });
+            }
+        }
+        MateLine { mv: m, mate_in, replies }
+    }
+
+    /// `max`수 안에 메이트하는 모든 수의 해답 트리
+    fn lines<S: GameState>(&mut self, state: &mut S, max: u8) -> Vec<MateLine<S::Move>> {
+        let mut lines = Vec::new();
+        for (m, mut child) in self.attacks(state) {
+            if let Some(mate_in) = self.shortest(&mut child, max) {
+                lines.push(self.line(m, child, mate_in));
+            }
+        }
+        lines.sort_by_key(|line| line.mate_in);
+        lines
+    }
+}
+
+/// 차례인 쪽이 `max_moves`수 안에 강제로 메이트하는지 증명하거나 반증하고, 해답 트리를 반환합니다.
+///
+/// 평가 함수를 쓰지 않고 모든 응수를 끝까지 확인하므로 결과는 정확합니다. (`MateConfig::checks_only` 참고)
+/// 해답 트리에는 상대의 모든 응수와, 그 뒤 메이트하는 모든 수가 들어 있습니다.
+pub fn solve_mate<S: GameState>(state: &mut S, max_moves: u8, config: &MateConfig) -> MateSearch<S::Move> {
+    let mut solver = Solver {
+        config,
+        nodes: 0,
+        aborted: false,
+        proven: HashMap::new(),
+        refuted: HashMap::new(),
+    };
+    if state.is_terminal() || !solver.mates_within(state, max_moves) {
+        return if solver.aborted { MateSearch::Aborted } else { MateSearch::NoMate };
+    }
+
+    let keys = solver.lines(state, max_moves);
+    if solver.aborted {
+        return MateSearch::Aborted;
+    }
+    let mate_in = keys.first().map_or(max_moves, |line| line.mate_in);
+    let shortest: Vec<&MateLine<S::Move>> = keys.iter().filter(|line| line.mate_in == mate_in).collect();
+    let duals = shortest.iter().map(|line| count_duals(line)).sum();
+    MateSearch::Mate(MateSolution {
+        mate_in,
+        unique: shortest.len() == 1 && duals == 0,
+        duals,
+        keys,
+    })
+}
+
+/// 해답 트리에서 해답이 둘 이상인 상대의 응수 수
+fn count_duals<M>(line: &MateLine<M>) -> usize {
+    line.replies
+        .iter()
+        .map(|reply| (reply.answers.len() > 1) as usize + reply.answers.iter().map(count_duals).sum::<usize>())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chessembly::board::{Board, BoardStatus};
+    use crate::chessembly::{ChessMove, ChessemblyCompiled, Color};
+
+    const CHAMELEON: &str = include_str!("../../chameleon.csm");
+
+    /// 백 킹과 룩들 대 흑 킹, 백 차례. 칸은 (x, y)이고 y = 0이 8번째 줄입니다.
+    fn rooks_vs_king<'a>(
+        compiled: &'a ChessemblyCompiled<'a>,
+        white_king: (usize, usize),
+        rooks: &[(usize, usize)],
+        black_king: (usize, usize),
+    ) -> Board<'a> {
+        let mut pieces = vec![(white_king, "king", Color::White), (black_king, "king", Color::Black)];
+        pieces.extend(rooks.iter().map(|&rook| (rook, "rook", Color::White)));
+        Board::from_pieces(compiled, Color::White, &pieces)
+    }
+
+    fn solve<'a>(board: &mut Board<'a>, max_moves: u8) -> MateSolution<ChessMove<'a>> {
+        match solve_mate(board, max_moves, &MateConfig::default()) {
+            MateSearch::Mate(solution) => solution,
+            other => panic!("메이트가 아닙니다: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn longer_keys_do_not_spoil_a_unique_mate_in_one() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        // 백 킹 b6, 룩 h2, 흑 킹 a8
+        let mut board = rooks_vs_king(&compiled, (1, 2), &[(7, 6)], (0, 0));
+        let solution = solve(&mut board, 2);
+        assert_eq!(solution.mate_in, 1);
+        assert!(solution.keys.len() > 1);
+        assert_eq!(solution.keys[0].mv.notation(), "h2h8");
+        assert!(solution.keys[0].replies.is_empty());
+        assert!(solution.unique);
+        assert_eq!(solution.duals, 0);
+    }
+
+    #[test]
+    fn mate_in_two_lists_every_defence() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        // 백 킹 c6, 룩 b1, 흑 킹 a8: 1. Kc7 Ka7 2. Ra1#
+        let mut board = rooks_vs_king(&compiled, (2, 2), &[(1, 7)], (0, 0));
+        assert!(matches!(solve_mate(&mut board, 1, &MateConfig::default()), MateSearch::NoMate));
+        let solution = solve(&mut board, 2);
+        assert_eq!(solution.mate_in, 2);
+        assert!(solution.unique);
+        let key = &solution.keys[0];
+        assert_eq!(key.mv.notation(), "c6c7");
+        assert_eq!(key.replies.len(), 1);
+        assert_eq!(key.replies[0].mv.notation(), "a8a7");
+        let answers: Vec<String> = key.replies[0].answers.iter().map(|line| line.mv.notation()).collect();
+        assert_eq!(answers, ["b1a1"]);
+    }
+
+    #[test]
+    fn duals_make_the_solution_not_unique() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        // 백 킹 e1, 룩 d7과 g7, 흑 킹 h8: 1. Rge7 Kg8 2. Rd8# 또는 Re8#
+        let mut board = rooks_vs_king(&compiled, (4, 7), &[(3, 1), (6, 1)], (7, 0));
+        let solution = solve(&mut board, 2);
+        assert_eq!(solution.mate_in, 2);
+        assert_eq!(solution.keys.iter().filter(|line| line.mate_in == 2).count(), 1);
+        assert_eq!(solution.keys[0].mv.notation(), "g7e7");
+        assert_eq!(solution.duals, 1);
+        assert!(!solution.unique);
+    }
+
+    #[test]
+    fn stalemate_is_not_mate() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        // 백 킹 b6, 룩 h7, 흑 킹 a8에서 Rh8#은 메이트이고 Rb7은 스테일메이트입니다.
+        let mut board = rooks_vs_king(&compiled, (1, 2), &[(7, 1)], (0, 0));
+        let stalemate = board.parse_move("h7b7").unwrap();
+        let mut after = board.make_move_new(&stalemate);
+        assert_eq!(after.status(), BoardStatus::Stalemate);
+        assert!(matches!(solve_mate(&mut after, 3, &MateConfig::default()), MateSearch::NoMate));
+
+        let solution = solve(&mut board, 1);
+        assert_eq!(solution.keys.len(), 1);
+        assert_eq!(solution.keys[0].mv.notation(), "h7h8");
+        assert!(solution.keys.iter().all(|line| line.mv != stalemate));
+    }
+
+    #[test]
+    fn node_limit_aborts() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let mut board = rooks_vs_king(&compiled, (2, 2), &[(1, 7)], (0, 0));
+        let config = MateConfig {
+            checks_only: false,
+            max_nodes: Some(10),
+        };
+        assert!(matches!(solve_mate(&mut board, 2, &config), MateSearch::Aborted));
+    }
+}
@@ -0,0 +1,374 @@
+use super::game_logic::GameState;
+use super::rng::Rng;
+use super::search::{mate_in, RootLine, SearchConfig, SearchResult, SearchStats};
+
+/// 몬테카를로 트리 탐색(UCT)의 설정.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MctsConfig {
+    /// UCT 탐험 상수. 클수록 덜 본 수를 더 자주 시도합니다.
+    pub exploration: f64,
+    /// 플레이아웃을 몇 수까지 둔 뒤 평가 함수로 승률을 어림할지
+    pub playout_depth: u8,
+    /// 켜면 플레이아웃에서 무작위 수 대신 이득이 가장 큰 잡기/승격을 먼저 둡니다.
+    pub heavy_playouts: bool,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            exploration: std::f64::consts::SQRT_2,
+            playout_depth: 6,
+            heavy_playouts: false,
+        }
+    }
+}
+
+/// 노드와 시간 한도가 모두 없을 때 최대 깊이 하나당 반복 횟수
+const ITERATIONS_PER_DEPTH: u64 = 1000;
+/// 무거운 플레이아웃에서 시끄러운 수가 있을 때 그중 최선을 둘 확률
+const HEAVY_GREEDY: f64 = 0.8;
+
+/// 평가 점수(센티폰)를 승률로 바꿉니다.
+fn win_probability(score: i32) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-(score as f64) / 400.0))
+}
+
+/// 승률을 다시 평가 점수로 바꿉니다. 결과와 주 변화 점수를 알파-베타와 같은 단위로 보여 주기 위함입니다.
+fn probability_score(p: f64) -> i32 {
+    let p = p.clamp(0.001, 0.999);
+    (-400.0 * (1.0 / p - 1.0).log10()).round() as i32
+}
+
+struct Node<S: GameState> {
+    state: S,
+    /// 부모에서 이 노드로 온 수 (루트는 없음)
+    mv: Option<S::Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// 아직 펼치지 않은 수. 처음 방문할 때 만듭니다. 점수가 높은 수가 끝에 옵니다.
+    untried: Option<Vec<S::Move>>,
+    visits: u32,
+    /// 이 노드로 수를 둔 쪽(부모의 차례)의 관점에서 본 결과의 합
+    wins: f64,
+    depth: u8,
+}
+
+struct Mcts<'l, 'c, S: GameState> {
+    config: &'l SearchConfig<'c>,
+    mcts: &'l MctsConfig,
+    tree: Vec<Node<S>>,
+    rng: Rng,
+    nodes: u64,
+}
+
+impl<'l, 'c, S: GameState> Mcts<'l, 'c, S> {
+    fn push(&mut self, state: S, mv: Option<S::Move>, parent: Option<usize>) -> usize {
+        let depth = parent.map_or(0, |p| self.tree[p].depth.saturating_add(1));
+        self.tree.push(Node {
+            state,
+            mv,
+            parent,
+            children: Vec::new(),
+            untried: None,
+            visits: 0,
+            wins: 0.0,
+            depth,
+        });
+        let id = self.tree.len() - 1;
+        if let Some(parent) = parent {
+            self.tree[parent].children.push(id);
+        }
+        id
+    }
+
+    /// 점수가 같은 수끼리는 무작위로 섞고, 점수가 높은 수부터 펼칩니다.
+    fn untried_moves(&mut self, id: usize) -> &mut Vec<S::Move> {
+        if self.tree[id].untried.is_none() {
+            let state = &mut self.tree[id].state;
+            let mut moves = state.get_legal_moves();
+            self.rng.shuffle(&mut moves);
+            moves.sort_by_cached_key(|m| state.score_move(m));
+            self.tree[id].untried = Some(moves);
+        }
+        self.tree[id].untried.as_mut().unwrap()
+    }
+
+    fn uct_child(&self, id: usize) -> usize {
+        let parent = &self.tree[id];
+        let log_visits = (parent.visits.max(1) as f64).ln();
+        let uct = |child: usize| {
+            let node = &self.tree[child];
+            node.wins / node.visits as f64
+                + self.mcts.exploration * (log_visits / node.visits as f64).sqrt()
+        };
+        parent
+            .children
+            .iter()
+            .copied()
+            .max_by(|a, b| uct(*a).total_cmp(&uct(*b)))
+            .unwrap()
+    }
+
+    /// 선택과 확장: UCT로 내려가다가 펼치지 않은 수가 있으면 하나 펼칩니다.
+    fn select(&mut self) -> usize {
+        let mut id = 0;
+        loop {
+            if self.tree[id].state.is_terminal() {
+                return id;
+            }
+            if let Some(m) = self.untried_moves(id).pop() {
+                let state = self.tree[id].state.make_move(&m);
+                self.nodes += 1;
+                return self.push(state, Some(m), Some(id));
+            }
+            if self.tree[id].children.is_empty() {
+                return id;
+            }
+            id = self.uct_child(id);
+        }
+    }
+
+    /// 시뮬레이션: 몇 수 두어 본 뒤 평가 함수로 어림한, `state`의 차례인 쪽의 승률
+    fn playout(&mut self, state: &S) -> f64 {
+        let mut state = state.clone();
+        let mut flipped = false;
+        for _ in 0..self.mcts.playout_depth {
+            if state.is_terminal() {
+                break;
+            }
+            let moves = state.get_legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            let mut chosen = None;
+            if self.mcts.heavy_playouts && self.rng.next_f64() < HEAVY_GREEDY {
+                chosen = moves
+                    .iter()
+                    .filter_map(|m| state.noisy_gain(m).map(|gain| (gain, m)))
+                    .max_by_key(|(gain, _)| *gain)
+                    .map(|(_, m)| m);
+            }
+            let chosen = chosen.unwrap_or_else(|| &moves[self.rng.below(moves.len())]);
+            state = state.make_move(chosen);
+            self.nodes += 1;
+            flipped = !flipped;
+        }
+        let score = match self.config.evaluator {
+            Some(evaluator) => state.evaluate_with(evaluator),
+            None => state.evaluate(),
+        };
+        let p = win_probability(score);
+        if flipped {
+            1.0 - p
+        } else {
+            p
+        }
+    }
+
+    /// 역전파: `value`는 `id` 노드의 차례인 쪽의 관점입니다.
+    fn backpropagate(&mut self, mut id: usize, value: f64) {
+        let mut result = 1.0 - value;
+        loop {
+            let node = &mut self.tree[id];
+            node.visits += 1;
+            node.wins += result;
+            result = 1.0 - result;
+            match node.parent {
+                Some(parent) => id = parent,
+                None => break,
+            }
+        }
+    }
+
+    fn should_stop(&self, start: f64, iterations: u64) -> bool {
+        let limits = &self.config.limits;
+        if let Some(max_nodes) = limits.max_nodes {
+            if self.nodes >= max_nodes {
+                return true;
+            }
+        }
+        // 한 번의 반복이 짧으므로 하드 한도까지 씁니다. 하드 한도가 없으면 소프트 한도에서 멈춥니다.
+        if let Some(deadline) = limits.hard_deadline.or(limits.soft_deadline) {
+            if limits.clock.now_ms() - start >= deadline {
+                return true;
+            }
+        }
+        let unbounded = limits.max_nodes.is_none() && limits.hard_deadline.is_none() && limits.soft_deadline.is_none();
+        unbounded && iterations >= ITERATIONS_PER_DEPTH * limits.max_depth.max(1) as u64
+    }
+
+    fn most_visited(&self, id: usize) -> Option<usize> {
+        self.tree[id].children.iter().copied().max_by_key(|&c| self.tree[c].visits)
+    }
+
+    /// 루트 자식 하나의 점수. 바로 메이트시키는 수는 알파-베타처럼 메이트 점수로 나타냅니다.
+    fn child_score(&mut self, child: usize) -> i32 {
+        let node = &mut self.tree[child];
+        if node.state.is_terminal() {
+            let score = -node.state.evaluate();
+            return if mate_in(score).is_some() { score - score.signum() } else { score };
+        }
+        probability_score(node.wins / node.visits.max(1) as f64)
+    }
+
+    fn line(&mut self, child: usize) -> RootLine<S::Move> {
+        let score = self.child_score(child);
+        let mut pv = vec![self.tree[child].mv.clone().unwrap()];
+        let mut id = child;
+        while let Some(next) = self.most_visited(id) {
+            pv.push(self.tree[next].mv.clone().unwrap());
+            id = next;
+        }
+        RootLine {
+            root_move: pv[0].clone(),
+            score,
+            mate_in: mate_in(score),
+            pv,
+        }
+    }
+}
+
+/// 몬테카를로 트리 탐색(UCT)으로 최선의 수를 찾습니다.
+/// 한도와 결과 형식은 `find_best_move`와 같고, 노드 수는 탐색 중 둔 수(트리 확장과 플레이아웃)의 개수입니다.
+/// 가장 많이 방문한 수를 두고, 결과의 `depth`는 트리가 가장 깊이 자란 깊이입니다.
+pub fn find_best_move_mcts<S: GameState>(
+    state: &mut S,
+    config: &SearchConfig,
+    mcts: &MctsConfig,
+) -> Result<SearchResult<S::Move>, usize> {
+    if state.is_terminal() {
+        return Err(260);
+    }
+
+    let mut search = Mcts {
+        config,
+        mcts,
+        tree: Vec::new(),
+        rng: Rng::new(config.seed),
+        nodes: 0,
+    };
+    search.push(state.clone(), None, None);
+    if search.untried_moves(0).is_empty() {
+        return Err(260);
+    }
+
+    let start = config.limits.clock.now_ms();
+    let mut iterations = 0;
+    // 루트의 수를 적어도 하나는 펼쳐야 결과를 낼 수 있습니다.
+    while iterations == 0 || !search.should_stop(start, iterations) {
+        let leaf = search.select();
+        let value = {
+            let leaf_state = search.tree[leaf].state.clone();
+            search.playout(&leaf_state)
+        };
+        search.backpropagate(leaf, value);
+        iterations += 1;
+    }
+
+    // 바로 메이트시키는 수를 먼저, 그다음은 방문 횟수 순으로 줄을 세웁니다.
+    let mut children = search.tree[0].children.clone();
+    let scores: Vec<i32> = children.iter().map(|&c| search.child_score(c)).collect();
+    let mut order: Vec<usize> = (0..children.len()).collect();
+    order.sort_by_key(|&i| {
+        let mate = mate_in(scores[i]).is_some_and(|n| n > 0);
+        std::cmp::Reverse((mate, search.tree[children[i]].visits))
+    });
+    children = order.into_iter().map(|i| children[i]).collect();
+
+    let lines: Vec<RootLine<S::Move>> = children
+        .into_iter()
+        .take(config.multi_pv.max(1))
+        .map(|c| search.line(c))
+        .collect();
+    let depth = search.tree.iter().map(|node| node.depth).max().unwrap_or(0);
+    let best = &lines[0];
+    Ok(SearchResult {
+        best_move: best.root_move.clone(),
+        score: best.score,
+        mate_in: best.mate_in,
+        depth,
+        pv: best.pv.clone(),
+        lines,
+        book: false,
+        stats: SearchStats {
+            nodes: search.nodes,
+            depth,
+            elapsed_ms: config.limits.clock.now_ms() - start,
+            ..SearchStats::default()
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chessembly::board::Board;
+    use crate::chessembly::{ChessemblyCompiled, Color};
+    use crate::engine::search::{find_best_move, SearchBackend, SearchLimits};
+
+    const CHAMELEON: &str = include_str!("../../chameleon.csm");
+
+    /// 백 킹 b6, 룩 h1, 흑 킹 a8, 백 차례: Rh8#
+    fn mate_in_one<'a>(compiled: &'a ChessemblyCompiled<'a>) -> Board<'a> {
+        Board::from_pieces(
+            compiled,
+            Color::White,
+            &[((1, 2), "king", Color::White), ((7, 7), "rook", Color::White), ((0, 0), "king", Color::Black)],
+        )
+    }
+
+    fn mcts_config(max_nodes: u64, seed: u64) -> SearchConfig<'static> {
+        SearchConfig {
+            limits: SearchLimits {
+                max_nodes: Some(max_nodes),
+                ..SearchLimits::depth(8)
+            },
+            seed,
+            backend: SearchBackend::Mcts(MctsConfig::default()),
+            ..SearchConfig::default()
+        }
+    }
+
+    #[test]
+    fn finds_a_mate_in_one() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let mut board = mate_in_one(&compiled);
+        let result = find_best_move(&mut board, &mcts_config(400, 0)).unwrap();
+        assert_eq!(result.best_move.notation(), "h1h8");
+        assert_eq!(result.mate_in, Some(1));
+    }
+
+    #[test]
+    fn stays_within_the_node_limit() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let mut board = Board::new(&compiled);
+        let result = find_best_move(&mut board, &mcts_config(150, 0)).unwrap();
+        // 한도는 반복 사이에 확인하므로 반복 하나(확장 + 플레이아웃)만큼 넘을 수 있습니다.
+        let per_iteration = 1 + MctsConfig::default().playout_depth as u64;
+        assert!(result.stats.nodes >= 150);
+        assert!(result.stats.nodes < 150 + per_iteration, "{}", result.stats.nodes);
+    }
+
+    #[test]
+    fn finished_games_are_an_error() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let board = mate_in_one(&compiled);
+        let mate = board.clone().get_legal_moves().into_iter().find(|m| m.notation() == "h1h8").unwrap();
+        let mut mated = board.make_move(&mate);
+        assert!(mated.is_terminal());
+        assert_eq!(find_best_move(&mut mated, &mcts_config(100, 0)).err(), Some(260));
+    }
+
+    #[test]
+    fn same_seed_plays_the_same_move() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let board = Board::new(&compiled);
+        for seed in 0..2 {
+            let play = || {
+                let result = find_best_move(&mut board.clone(), &mcts_config(100, seed)).unwrap();
+                (result.best_move.notation(), result.score, result.stats.nodes)
+            };
+            assert_eq!(play(), play(), "시드 {seed}");
+        }
+    }
+}
@@ -0,0 +1,78 @@
+/// 시드로 재현 가능한 작은 의사 난수 생성기 (SplitMix64).
+/// JS 런타임에 의존하지 않으므로 네이티브 테스트에서도 같은 결과를 냅니다.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// [0, 1) 범위의 실수
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// [0, n) 범위의 정수. `n`은 0보다 커야 합니다.
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_splitmix64_reference() {
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_sequence() {
+        let (mut a, mut b) = (Rng::new(42), Rng::new(42));
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Rng::new(42).next_u64(), Rng::new(43).next_u64());
+    }
+
+    #[test]
+    fn ranges_and_shuffle() {
+        let mut rng = Rng::new(7);
+        for n in [1, 2, 3, 10, 1000] {
+            for _ in 0..100 {
+                assert!(rng.below(n) < n);
+                let x = rng.next_f64();
+                assert!((0.0..1.0).contains(&x));
+            }
+        }
+        let mut items: Vec<u32> = (0..20).collect();
+        Rng::new(7).shuffle(&mut items);
+        let mut again: Vec<u32> = (0..20).collect();
+        Rng::new(7).shuffle(&mut again);
+        assert_eq!(items, again);
+        assert_ne!(items, (0..20).collect::<Vec<u32>>());
+        items.sort_unstable();
+        assert_eq!(items, (0..20).collect::<Vec<u32>>());
+    }
+}
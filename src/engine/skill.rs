@@ -0,0 +1,156 @@
+use super::search::SearchConfig;
+
+/// 난이도 하나의 설정. `apply`로 탐색 설정에 덮어씁니다.
+/// 단계는 `SKILL_LEVELS`에서의 순서(1부터)입니다.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkillLevel {
+    /// 어림잡은 Elo. 잰 값이 아니라 단계 사이의 간격을 짐작해 최고 난이도를 2000으로 두고 적은 값이므로,
+    /// 단계의 순서를 보이는 데만 쓰세요. 실제 차이는 `bin/skill`로 재어 볼 수 있습니다.
+    pub elo: i32,
+    pub max_depth: u8,
+    pub max_nodes: u64,
+    /// 정적 평가에 더할 잡음의 최대 크기 (센티폰)
+    pub eval_noise: i32,
+    /// 최선의 수와 이 점수 이내로 차이 나는 수 중에서 고릅니다. 0이면 항상 최선의 수.
+    pub variety: i32,
+    /// 점수 차이에 따른 선택 확률의 온도. 낮을수록 최선의 수에 가깝게 둡니다.
+    pub temperature: i32,
+    /// 최선의 수 대신 눈에 띄게 못한 수를 둘 확률
+    pub blunder_rate: f64,
+}
+
+const fn level(
+    elo: i32,
+    max_depth: u8,
+    max_nodes: u64,
+    eval_noise: i32,
+    variety: i32,
+    temperature: i32,
+    blunder_rate: f64,
+) -> SkillLevel {
+    SkillLevel {
+        elo,
+        max_depth,
+        max_nodes,
+        eval_noise,
+        variety,
+        temperature,
+        blunder_rate,
+    }
+}
+
+/// 난이도 표. 10단계는 제한 없는 기본 탐색과 같습니다. Elo는 어림값입니다. (`SkillLevel::elo` 참고)
+#[rustfmt::skip]
+pub const SKILL_LEVELS: [SkillLevel; 10] = [
+    //    Elo(어림) 깊이 노드  잡음 범위 온도 실수
+    level(1000, 1, 50,   300, 400, 200, 0.25),
+    level(1150, 1, 100,  200, 300, 150, 0.18),
+    level(1300, 2, 200,  150, 200, 100, 0.12),
+    level(1450, 2, 300,  100, 150, 80,  0.08),
+    level(1600, 3, 500,  70,  100, 60,  0.05),
+    level(1650, 3, 800,  50,  70,  40,  0.03),
+    level(1750, 4, 1200, 30,  40,  25,  0.015),
+    level(1800, 5, 1500, 15,  20,  15,  0.005),
+    level(1850, 6, 2000, 5,   10,  10,  0.0),
+    level(2000, u8::MAX, u64::MAX, 0, 0, 0, 0.0),
+];
+
+/// 1부터 10까지의 난이도. 범위를 벗어나면 `None`.
+pub fn skill_level(level: u8) -> Option<&'static SkillLevel> {
+    SKILL_LEVELS.get(usize::from(level).checked_sub(1)?)
+}
+
+impl SkillLevel {
+    /// 탐색 설정을 이 난이도로 약하게 만듭니다. 이미 있는 한도와 약하게 만드는 설정은 느슨해지지 않으며,
+    /// 아무것도 약하게 하지 않는 10단계는 설정을 바꾸지 않습니다.
+    pub fn apply(&self, config: &mut SearchConfig) {
+        let limits = &mut config.limits;
+        limits.max_depth = limits.max_depth.min(self.max_depth);
+        if self.max_nodes < u64::MAX {
+            limits.max_nodes = Some(limits.max_nodes.map_or(self.max_nodes, |nodes| nodes.min(self.max_nodes)));
+        }
+        config.eval_noise = config.eval_noise.max(self.eval_noise);
+        if self.variety > 0 {
+            config.root_variety = Some(config.root_variety.map_or(self.variety, |variety| variety.max(self.variety)));
+        }
+        if self.temperature > 0 {
+            config.root_temperature = Some(self.temperature);
+        }
+        config.blunder_rate = config.blunder_rate.max(self.blunder_rate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chessembly::board::Board;
+    use crate::chessembly::ChessemblyCompiled;
+    use crate::engine::search::{find_best_move, SearchLimits};
+
+    const CHAMELEON: &str = include_str!("../../chameleon.csm");
+
+    #[test]
+    fn levels_never_loosen_existing_limits() {
+        for skill in &SKILL_LEVELS {
+            let mut config = SearchConfig {
+                limits: SearchLimits {
+                    max_nodes: Some(40),
+                    ..SearchLimits::depth(1)
+                },
+                eval_noise: 500,
+                root_variety: Some(1000),
+                blunder_rate: 0.5,
+                ..SearchConfig::default()
+            };
+            skill.apply(&mut config);
+            assert_eq!(config.limits.max_depth, 1);
+            assert_eq!(config.limits.max_nodes, Some(40));
+            assert_eq!(config.eval_noise, 500);
+            assert_eq!(config.root_variety, Some(1000));
+            assert_eq!(config.blunder_rate, 0.5);
+        }
+
+        // 한도가 없으면 난이도의 한도를 씁니다.
+        let mut config = SearchConfig {
+            limits: SearchLimits::depth(u8::MAX),
+            ..SearchConfig::default()
+        };
+        skill_level(3).unwrap().apply(&mut config);
+        assert_eq!((config.limits.max_depth, config.limits.max_nodes), (2, Some(200)));
+        assert_eq!(config.root_variety, Some(200));
+    }
+
+    #[test]
+    fn the_top_level_changes_nothing() {
+        let top = skill_level(SKILL_LEVELS.len() as u8).unwrap();
+        for variety in [None, Some(30)] {
+            let mut config = SearchConfig {
+                limits: SearchLimits::depth(6),
+                root_variety: variety,
+                ..SearchConfig::default()
+            };
+            top.apply(&mut config);
+            assert_eq!((config.limits.max_depth, config.limits.max_nodes), (6, None));
+            assert_eq!((config.eval_noise, config.root_variety, config.root_temperature), (0, variety, None));
+            assert_eq!(config.blunder_rate, 0.0);
+        }
+        assert!(skill_level(0).is_none() && skill_level(11).is_none());
+    }
+    #[test]
+    fn a_low_level_changes_the_chosen_move() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let board = Board::new(&compiled);
+        let play = |level: u8, seed| {
+            let mut config = SearchConfig {
+                limits: SearchLimits::depth(2),
+                seed,
+                ..SearchConfig::default()
+            };
+            skill_level(level).unwrap().apply(&mut config);
+            find_best_move(&mut board.clone(), &config).unwrap().best_move.notation()
+        };
+        // 같은 시드에서 10단계는 가장 좋은 수를, 1단계는 다른 수를 둡니다.
+        assert_eq!(play(10, 1), "e2e4");
+        assert_eq!(play(1, 1), "a2a3");
+    }
+}
@@ -0,0 +1,644 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::chessembly::board::Board;
+use crate::chessembly::{ChessemblyCompiled, Color, MoveGen, Piece, PieceSpan};
+
+/// 테이블 하나에 넣을 수 있는 최대 기물 수 (킹 포함).
+/// 위치 수가 기물마다 64배로 늘고, 수를 만드는 스크립트 해석이 느려서 넷부터는 현실적이지 않습니다.
+pub const MAX_PIECES: usize = 3;
+
+// 위치마다 한 바이트: 아래 세 값이 아니면 `DTM_BASE + 메이트까지의 플라이`.
+// 플라이가 짝수면 차례인 쪽이 지고, 홀수면 이깁니다.
+const DRAW: u8 = 0;
+/// 기물이 겹치거나 차례가 아닌 쪽이 체크인 위치
+const ILLEGAL: u8 = 1;
+/// 결과를 정할 수 없는 위치 (아직 만들고 있는 다른 테이블에 기대는 경우 등)
+const UNKNOWN: u8 = 2;
+const DTM_BASE: u8 = 3;
+const MAX_DTM: usize = (u8::MAX - DTM_BASE) as usize;
+
+// 바이너리 형식 (모두 리틀 엔디언):
+//   머리: "CSMT", 버전(u8), 스크립트 해시(u64), 테이블 수(u32)
+//   테이블: 기물 구성 길이(u16), 기물 구성(UTF-8), 위치 수(u32), 위치마다 한 바이트
+const MAGIC: &[u8; 4] = b"CSMT";
+/// 2: 스크립트 해시를 `Behavior::canonical`로 적은 글로 계산합니다. 1의 해시는 `Debug` 출력에 묶여 있었습니다.
+const VERSION: u8 = 2;
+
+/// 차례인 쪽이 본 결과. 숫자는 메이트까지의 플라이입니다.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Win(u8),
+    Draw,
+    Loss(u8),
+}
+
+fn decode(value: u8) -> Option<Outcome> {
+    match value {
+        DRAW => Some(Outcome::Draw),
+        ILLEGAL | UNKNOWN => None,
+        _ => {
+            let plies = value - DTM_BASE;
+            Some(if plies.is_multiple_of(2) { Outcome::Loss(plies) } else { Outcome::Win(plies) })
+        }
+    }
+}
+
+/// 한쪽 차례의 위치들을 결과별로 센 것
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TableSummary {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub unknown: usize,
+    /// 가장 긴 메이트 (플라이)
+    pub longest_mate: u8,
+}
+
+/// 기물 구성 하나의 테이블.
+/// 기물 구성은 `백 기물들/흑 기물들`이며 각 쪽은 이름순으로 쉼표로 잇습니다. (예: `chameleon,king/king`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Table {
+    pub material: String,
+    /// 위치 번호를 만들 때의 기물 순서
+    slots: Vec<(String, Color)>,
+    values: Vec<u8>,
+}
+
+impl Table {
+    fn new(material: &str, values: Vec<u8>) -> Option<Table> {
+        let slots = parse_material(material)?;
+        (values.len() == position_count(slots.len())).then(|| Table {
+            material: material.to_string(),
+            slots,
+            values,
+        })
+    }
+
+    /// 위치 번호: 기물마다 칸(y * 8 + x)을 64진수 자리로 쌓고, 마지막 비트는 흑 차례입니다.
+    /// 같은 기물이 둘 이상이면 보드에서 먼저 나오는 것부터 자리를 채웁니다.
+    fn index_of(slots: &[(String, Color)], board: &Board) -> Option<usize> {
+        let mut squares = vec![None; slots.len()];
+        for (square, span) in board.board.iter().flatten().enumerate() {
+            let PieceSpan::Piece(piece) = span else {
+                continue;
+            };
+            let slot = slots
+                .iter()
+                .enumerate()
+                .position(|(i, (name, color))| squares[i].is_none() && name == piece.piece_type && *color == piece.color)?;
+            squares[slot] = Some(square);
+        }
+        let mut index = 0;
+        for square in squares.iter().rev() {
+            index = index * 64 + (*square)?;
+        }
+        Some(index << 1 | (board.side_to_move() == Color::Black) as usize)
+    }
+
+    pub fn probe(&self, board: &Board) -> Option<Outcome> {
+        decode(self.values[Table::index_of(&self.slots, board)?])
+    }
+
+    /// 백 차례와 흑 차례 위치의 결과 요약
+    pub fn summary(&self) -> [TableSummary; 2] {
+        let mut summary = [TableSummary::default(); 2];
+        for (index, &value) in self.values.iter().enumerate() {
+            let side = &mut summary[index & 1];
+            match value {
+                ILLEGAL => {}
+                UNKNOWN => side.unknown += 1,
+                _ => match decode(value) {
+                    Some(Outcome::Win(plies)) => {
+                        side.wins += 1;
+                        side.longest_mate = side.longest_mate.max(plies);
+                    }
+                    Some(Outcome::Loss(plies)) => {
+                        side.losses += 1;
+                        side.longest_mate = side.longest_mate.max(plies);
+                    }
+                    _ => side.draws += 1,
+                },
+            }
+        }
+        summary
+    }
+}
+
+fn position_count(pieces: usize) -> usize {
+    2 << (6 * pieces)
+}
+
+/// `king,chameleon/king` 같은 기물 구성을 읽어 정해진 순서로 늘어놓습니다.
+fn parse_material(material: &str) -> Option<Vec<(String, Color)>> {
+    let (white, black) = material.split_once('/')?;
+    let mut slots = Vec::new();
+    for (side, color) in [(white, Color::White), (black, Color::Black)] {
+        let mut names: Vec<&str> = side.split(',').map(str::trim).filter(|name| !name.is_empty()).collect();
+        names.sort_unstable();
+        slots.extend(names.into_iter().map(|name| (name.to_string(), color)));
+    }
+    Some(slots)
+}
+
+fn material_key(slots: &[(String, Color)]) -> String {
+    let side = |color| {
+        let names: Vec<&str> = slots.iter().filter(|(_, c)| *c == color).map(|(name, _)| name.as_str()).collect();
+        names.join(",")
+    };
+    format!("{}/{}", side(Color::White), side(Color::Black))
+}
+
+fn board_material(board: &Board) -> String {
+    let mut slots: Vec<(String, Color)> = board
+        .board
+        .iter()
+        .flatten()
+        .filter_map(|span| match span {
+            PieceSpan::Piece(piece) => Some((piece.piece_type.to_string(), piece.color)),
+            PieceSpan::Empty => None,
+        })
+        .collect();
+    slots.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    material_key(&slots)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TablebaseError {
+    BadMaterial(String),
+    TooManyPieces(usize),
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+}
+
+impl fmt::Display for TablebaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TablebaseError::BadMaterial(material) => write!(f, "기물 구성을 읽을 수 없습니다: {material}"),
+            TablebaseError::TooManyPieces(count) => write!(f, "기물이 {count}개입니다. 최대 {MAX_PIECES}개까지 만들 수 있습니다"),
+            TablebaseError::BadMagic => write!(f, "테이블베이스 파일이 아닙니다"),
+            TablebaseError::UnsupportedVersion(version) => write!(f, "지원하지 않는 테이블베이스 버전: {version}"),
+            TablebaseError::Truncated => write!(f, "테이블베이스 파일이 잘렸습니다"),
+        }
+    }
+}
+
+/// 한 스크립트의 엔드게임 테이블들. 기물 구성이 맞는 위치는 완벽한 결과와 메이트까지의 거리를 압니다.
+///
+/// 캐슬링, 앙파상과 레지스터는 보지 않고 기물 배치와 차례만으로 위치를 구별합니다.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Tablebases {
+    /// 이 테이블들을 만든 스크립트의 `ChessemblyCompiled::script_hash`
+    pub script_hash: u64,
+    tables: HashMap<String, Table>,
+    max_pieces: usize,
+}
+
+impl Tablebases {
+    pub fn new(script_hash: u64) -> Tablebases {
+        Tablebases {
+            script_hash,
+            ..Tablebases::default()
+        }
+    }
+
+    pub fn table(&self, material: &str) -> Option<&Table> {
+        let slots = parse_material(material)?;
+        self.tables.get(&material_key(&slots))
+    }
+
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.values()
+    }
+
+    fn insert(&mut self, table: Table) {
+        self.max_pieces = self.max_pieces.max(table.slots.len());
+        self.tables.insert(table.material.clone(), table);
+    }
+
+    /// 기물 구성이 맞는 테이블이 있으면 이 위치의 결과를 찾습니다.
+    pub fn probe(&self, board: &Board) -> Option<Outcome> {
+        let pieces = board.board.iter().flatten().filter(|span| matches!(span, PieceSpan::Piece(_))).count();
+        if pieces > self.max_pieces {
+            return None;
+        }
+        self.tables.get(&board_material(board))?.probe(board)
+    }
+
+    /// 기물 구성의 테이블을 만듭니다.
+    /// 잡기나 변신으로 넘어가는 기물 구성의 테이블도 없으면 함께 만듭니다.
+    pub fn generate(&mut self, script: &ChessemblyCompiled, material: &str) -> Result<(), TablebaseError> {
+        let slots = parse_material(material).ok_or_else(|| TablebaseError::BadMaterial(material.to_string()))?;
+        if slots.len() > MAX_PIECES {
+            return Err(TablebaseError::TooManyPieces(slots.len()));
+        }
+        self.generate_table(script, slots, &mut Vec::new());
+        Ok(())
+    }
+
+    fn generate_table(&mut self, script: &ChessemblyCompiled, slots: Vec<(String, Color)>, in_progress: &mut Vec<String>) {
+        let material = material_key(&slots);
+        if self.tables.contains_key(&material) {
+            return;
+        }
+        in_progress.push(material.clone());
+
+        let count = position_count(slots.len());
+        let mut values = vec![UNKNOWN; count];
+        // 같은 기물 구성 안에서 갈 수 있는 위치들 (CSR 형식)
+        let mut child_start = vec![0u32; count + 1];
+        let mut children: Vec<u32> = Vec::new();
+        // 다른 기물 구성으로 넘어가는 수의 결과 (자식의 차례인 쪽 관점)
+        let mut external: Vec<(u32, Option<Outcome>)> = Vec::new();
+
+        for index in 0..count {
+            child_start[index] = children.len() as u32;
+            let Some(mut board) = setup(script, &slots, index) else {
+                values[index] = ILLEGAL;
+                continue;
+            };
+            let turn = board.side_to_move();
+            if script.is_check(&mut board, turn) {
+                values[index] = ILLEGAL;
+                continue;
+            }
+            let moves = MoveGen::new_legal(&mut board);
+            if moves.is_empty() {
+                values[index] = if script.is_check(&mut board, turn.invert()) { DTM_BASE } else { DRAW };
+                continue;
+            }
+            for m in &moves {
+                let mut child = board.make_move_new_nc(m, false);
+                child.turn = turn.invert();
+                // 잡기나 변신이 있어도 기물 구성이 그대로면(같은 기물로 변신 등) 이 테이블 안의 위치입니다.
+                let same_material = (board.piece_on(&m.take).is_none() && m.transition.is_none())
+                    || board_material(&child) == material;
+                match same_material.then(|| Table::index_of(&slots, &child)).flatten() {
+                    Some(child_index) => children.push(child_index as u32),
+                    None => {
+                        let outcome = self.external_outcome(script, &mut child, in_progress);
+                        external.push((index as u32, outcome));
+                    }
+                }
+            }
+        }
+        child_start[count] = children.len() as u32;
+
+        retrograde(&mut values, &child_start, &children, &external);
+        in_progress.pop();
+        self.insert(Table { material, slots, values });
+    }
+
+    /// 다른 기물 구성으로 넘어간 위치의 결과. 그 테이블이 만들어지는 중이면(서로 변신하는 기물 등) 알 수 없습니다.
+    fn external_outcome(
+        &mut self,
+        script: &ChessemblyCompiled,
+        child: &mut Board,
+        in_progress: &mut Vec<String>,
+    ) -> Option<Outcome> {
+        let key = board_material(child);
+        if in_progress.contains(&key) {
+            return None;
+        }
+        if !self.tables.contains_key(&key) {
+            let slots = parse_material(&key)?;
+            self.generate_table(script, slots, in_progress);
+        }
+        self.tables.get(&key)?.probe(child)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.script_hash.to_le_bytes());
+        bytes.extend_from_slice(&(self.tables.len() as u32).to_le_bytes());
+        let mut tables: Vec<&Table> = self.tables.values().collect();
+        tables.sort_by(|a, b| a.material.cmp(&b.material));
+        for table in tables {
+            bytes.extend_from_slice(&(table.material.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(table.material.as_bytes());
+            bytes.extend_from_slice(&(table.values.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&table.values);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Tablebases, TablebaseError> {
+        let mut rest = bytes;
+        let mut take = |n: usize| -> Result<&[u8], TablebaseError> {
+            if rest.len() < n {
+                return Err(TablebaseError::Truncated);
+            }
+            let (head, tail) = rest.split_at(n);
+            rest = tail;
+            Ok(head)
+        };
+        if take(4).map_err(|_| TablebaseError::BadMagic)? != MAGIC {
+            return Err(TablebaseError::BadMagic);
+        }
+        let version = take(1)?[0];
+        if version != VERSION {
+            return Err(TablebaseError::UnsupportedVersion(version));
+        }
+        let mut tablebases = Tablebases::new(u64::from_le_bytes(take(8)?.try_into().unwrap()));
+        let count = u32::from_le_bytes(take(4)?.try_into().unwrap());
+        for _ in 0..count {
+            let length = u16::from_le_bytes(take(2)?.try_into().unwrap()) as usize;
+            let material = String::from_utf8_lossy(take(length)?).into_owned();
+            let positions = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+            let values = take(positions)?.to_vec();
+            let table = Table::new(&material, values).ok_or(TablebaseError::BadMaterial(material))?;
+            tablebases.insert(table);
+        }
+        Ok(tablebases)
+    }
+}
+
+/// 위치 번호의 보드. 기물이 겹치거나 폰이 첫 줄이나 끝 줄에 있으면 `None`.
+fn setup<'a>(script: &'a ChessemblyCompiled<'a>, slots: &'a [(String, Color)], index: usize) -> Option<Board<'a>> {
+    let mut board = Board::empty(script);
+    for state in [&mut board.board_state.white, &mut board.board_state.black] {
+        state.castling_oo = false;
+        state.castling_ooo = false;
+    }
+    board.turn = if index & 1 == 1 { Color::Black } else { Color::White };
+    let mut squares = index >> 1;
+    for (name, color) in slots {
+        let square = squares % 64;
+        squares /= 64;
+        let span = &mut board.board[square / 8][square % 8];
+        // 폰은 첫 줄과 끝 줄에 있을 수 없습니다. (스크립트의 폰 규칙이 보드 밖을 가리킵니다)
+        if matches!(span, PieceSpan::Piece(_)) || (name == "pawn" && !(8..56).contains(&square)) {
+            return None;
+        }
+        *span = PieceSpan::Piece(Piece {
+            piece_type: name,
+            color: *color,
+        });
+    }
+    Some(board)
+}
+
+/// 역행 분석: 메이트 당한 위치부터 거리순으로, 어느 수로든 지는 위치로 가면 이기고
+/// 모든 수가 이기는 위치로 가면 지는 것을 거꾸로 퍼뜨립니다.
+fn retrograde(values: &mut [u8], child_start: &[u32], children: &[u32], external: &[(u32, Option<Outcome>)]) {
+    let count = values.len();
+    let edges = |index: usize| &children[child_start[index] as usize..child_start[index + 1] as usize];
+
+    // 부모 목록 (CSR 형식)
+    let mut parent_start = vec![0u32; count + 1];
+    for &child in children {
+        parent_start[child as usize + 1] += 1;
+    }
+    for i in 0..count {
+        parent_start[i + 1] += parent_start[i];
+    }
+    let mut parents = vec![0u32; children.len()];
+    let mut fill = parent_start.clone();
+    for index in 0..count {
+        for &child in edges(index) {
+            parents[fill[child as usize] as usize] = index as u32;
+            fill[child as usize] += 1;
+        }
+    }
+    let parents_of = |index: usize| &parents[parent_start[index] as usize..parent_start[index + 1] as usize];
+
+    // 아직 이기는 위치로 확인되지 않은 자식 수. 0이 되면 지는 위치입니다.
+    let mut remaining: Vec<u32> = (0..count).map(|i| edges(i).len() as u32).collect();
+    // 비기거나 알 수 없는 자식이 있어 질 수 없는 위치
+    let mut blocked = vec![false; count];
+    let mut depends_on_unknown = vec![false; count];
+
+    // buckets[d]: (부모, 자식이 졌는지) — 거리 d에서 결과가 정해진 자식들
+    let mut buckets: Vec<Vec<(u32, bool)>> = vec![Vec::new()];
+    fn push(buckets: &mut Vec<Vec<(u32, bool)>>, plies: usize, event: (u32, bool)) {
+        if buckets.len() <= plies {
+            buckets.resize_with(plies + 1, Vec::new);
+        }
+        buckets[plies].push(event);
+    }
+    for &(index, outcome) in external {
+        let index = index as usize;
+        remaining[index] += 1;
+        match outcome {
+            Some(Outcome::Loss(plies)) => push(&mut buckets, plies as usize, (index as u32, true)),
+            Some(Outcome::Win(plies)) => push(&mut buckets, plies as usize, (index as u32, false)),
+            Some(Outcome::Draw) => blocked[index] = true,
+            None => {
+                blocked[index] = true;
+                depends_on_unknown[index] = true;
+            }
+        }
+    }
+    // 메이트 당한 위치의 부모는 한 수 만에 메이트시킵니다.
+    for (index, _) in values.iter().enumerate().filter(|(_, &value)| value == DTM_BASE) {
+        for &parent in parents_of(index) {
+            push(&mut buckets, 0, (parent, true));
+        }
+    }
+
+    let mut plies = 0;
+    while plies < buckets.len() && plies < MAX_DTM {
+        for (index, child_lost) in std::mem::take(&mut buckets[plies]) {
+            let index = index as usize;
+            if values[index] != UNKNOWN {
+                continue;
+            }
+            let decided = if child_lost {
+                true
+            } else {
+                remaining[index] -= 1;
+                remaining[index] == 0 && !blocked[index]
+            };
+            if decided {
+                values[index] = DTM_BASE + plies as u8 + 1;
+                for &parent in parents_of(index) {
+                    push(&mut buckets, plies + 1, (parent, !child_lost));
+                }
+            }
+        }
+        plies += 1;
+    }
+
+    // 정해지지 않은 위치는 비기지만, 알 수 없는 결과에 기대는 위치는 알 수 없습니다.
+    // `MAX_DTM`에서 멈춰 아직 처리하지 못한 결과가 남은 위치도 더 긴 메이트일 수 있으므로 알 수 없습니다.
+    let truncated = buckets.iter().skip(plies).flatten().map(|&(index, _)| index as usize);
+    let mut stack: Vec<usize> = (0..count)
+        .filter(|&i| depends_on_unknown[i])
+        .chain(truncated)
+        .filter(|&i| values[i] == UNKNOWN)
+        .collect();
+    let mut unknown = vec![false; count];
+    while let Some(index) = stack.pop() {
+        if unknown[index] {
+            continue;
+        }
+        unknown[index] = true;
+        stack.extend(parents_of(index).iter().map(|&p| p as usize).filter(|&p| values[p] == UNKNOWN && !unknown[p]));
+    }
+    for (value, unknown) in values.iter_mut().zip(unknown) {
+        if *value == UNKNOWN && !unknown {
+            *value = DRAW;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAMELEON: &str = include_str!("../../chameleon.csm");
+
+    fn rook_endgame<'a>(compiled: &'a ChessemblyCompiled<'a>, turn: Color, pieces: [(usize, usize); 3]) -> Board<'a> {
+        Board::from_pieces(
+            compiled,
+            turn,
+            &[(pieces[0], "king", Color::White), (pieces[1], "rook", Color::White), (pieces[2], "king", Color::Black)],
+        )
+    }
+
+    /// 퀸과 나이트처럼 움직이는 기물 하나뿐인 스크립트. 킹이 없는 쪽은 체크 당하지 않으므로
+    /// 아마존 하나 대 킹의 두 기물 테이블(8192개 위치)로도 메이트가 나옵니다.
+    const AMAZON: &str = "piece(amazon) take-move(1, 0) repeat(1); piece(amazon) take-move(-1, 0) repeat(1);
+        piece(amazon) take-move(0, 1) repeat(1); piece(amazon) take-move(0, -1) repeat(1);
+        piece(amazon) take-move(1, 1) repeat(1); piece(amazon) take-move(-1, 1) repeat(1);
+        piece(amazon) take-move(1, -1) repeat(1); piece(amazon) take-move(-1, -1) repeat(1);
+        piece(amazon) take-move(2, 1); piece(amazon) take-move(2, -1); piece(amazon) take-move(-2, 1);
+        piece(amazon) take-move(-2, -1); piece(amazon) take-move(1, 2); piece(amazon) take-move(1, -2);
+        piece(amazon) take-move(-1, 2); piece(amazon) take-move(-1, -2);";
+
+    fn amazon_endgame<'a>(
+        compiled: &'a ChessemblyCompiled<'a>,
+        turn: Color,
+        amazon: (usize, usize),
+        king: (usize, usize),
+    ) -> Board<'a> {
+        Board::from_pieces(compiled, turn, &[(amazon, "amazon", Color::White), (king, "king", Color::Black)])
+    }
+
+    #[test]
+    fn a_lone_amazon_mates_a_lone_king() {
+        let compiled = ChessemblyCompiled::from_script(AMAZON).unwrap();
+        let mut tables = Tablebases::new(compiled.script_hash());
+        tables.generate(&compiled, "amazon/king").unwrap();
+        // 킹이 아마존을 잡으면 넘어가는 테이블도 만들어지고, 모두 비깁니다.
+        let [white, black] = tables.table("/king").unwrap().summary();
+        assert_eq!((white.wins + black.wins, white.losses + black.losses), (0, 0));
+
+        // 흑 킹 a8, 아마존 c5: Qc7#
+        let board = amazon_endgame(&compiled, Color::White, (2, 3), (0, 0));
+        assert_eq!(tables.probe(&board), Some(Outcome::Win(1)));
+        let board = amazon_endgame(&compiled, Color::Black, (2, 1), (0, 0));
+        assert_eq!(tables.probe(&board), Some(Outcome::Loss(0)));
+        // 지켜 주는 기물이 없으므로 옆에 있는 아마존을 잡아 비깁니다.
+        let board = amazon_endgame(&compiled, Color::Black, (4, 3), (3, 4));
+        assert_eq!(tables.probe(&board), Some(Outcome::Draw));
+
+        let table = tables.table("amazon/king").unwrap();
+        let [white, black] = table.summary();
+        assert!(white.wins > 0 && black.losses > 0);
+        assert_eq!((white.losses, black.wins, white.unknown, black.unknown), (0, 0, 0, 0));
+
+        // 역행 분석이 맞는지 위치들을 한 수 앞의 결과와 맞춰 봅니다. (디버그 빌드에서도 빠르도록 세 개에 하나씩)
+        // n플라이 만에 이기면 n - 1플라이 만에 지는 자식이 있고, n플라이 만에 지면 모든 자식이 이기며 가장 긴 것이 n - 1입니다.
+        for index in (0..table.values.len()).step_by(3) {
+            let Some(outcome) = decode(table.values[index]) else {
+                continue;
+            };
+            let mut board = setup(&compiled, &table.slots, index).unwrap();
+            let turn = board.side_to_move();
+            let children: Vec<Option<Outcome>> = MoveGen::new_legal(&mut board)
+                .iter()
+                .map(|m| {
+                    let mut child = board.make_move_new_nc(m, false);
+                    child.turn = turn.invert();
+                    tables.probe(&child)
+                })
+                .collect();
+            match outcome {
+                Outcome::Win(plies) => assert!(children.contains(&Some(Outcome::Loss(plies - 1))), "{index}"),
+                Outcome::Loss(0) => assert!(children.is_empty(), "{index}"),
+                Outcome::Loss(plies) => {
+                    let wins: Option<Vec<u8>> = children
+                        .iter()
+                        .map(|child| match child {
+                            Some(Outcome::Win(n)) => Some(*n),
+                            _ => None,
+                        })
+                        .collect();
+                    assert_eq!(wins.and_then(|wins| wins.into_iter().max()), Some(plies - 1), "{index}");
+                }
+                Outcome::Draw => {
+                    assert!(!children.iter().any(|child| matches!(child, Some(Outcome::Loss(_)))), "{index}");
+                    let all_win = children.iter().all(|child| matches!(child, Some(Outcome::Win(_))));
+                    assert!(children.is_empty() || !all_win, "{index}");
+                }
+            }
+        }
+    }
+
+    /// 체스 킹과 룩의 테이블은 위치가 50만 개가 넘어 디버그 빌드로는 몇 분 걸립니다.
+    /// `cargo test --release -- --ignored`나 `bin/tablebase`로 확인하세요.
+    #[test]
+    #[ignore]
+    fn king_and_rook_mate_a_lone_king() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let mut tables = Tablebases::new(compiled.script_hash());
+        tables.generate(&compiled, "king,rook/king").unwrap();
+        // 잡기로 넘어가는 킹 대 킹 테이블도 만들어지고, 모두 비깁니다.
+        let [white, black] = tables.table("king/king").unwrap().summary();
+        assert_eq!((white.wins, white.losses, white.unknown), (0, 0, 0));
+        assert_eq!((black.wins, black.losses, black.unknown), (0, 0, 0));
+
+        // 백 킹 b6, 룩 h2, 흑 킹 a8: Rh8#
+        let board = rook_endgame(&compiled, Color::White, [(1, 2), (7, 6), (0, 0)]);
+        assert_eq!(tables.probe(&board), Some(Outcome::Win(1)));
+        // 흑 차례면 흑은 메이트 당하지 않았고, 룩을 잡을 수도 없습니다.
+        let board = rook_endgame(&compiled, Color::Black, [(1, 2), (7, 6), (0, 0)]);
+        assert!(matches!(tables.probe(&board), Some(Outcome::Loss(plies)) if plies >= 2));
+        // 흑 킹이 룩 옆에 있고 백 킹이 멀면 흑이 룩을 잡아 비깁니다.
+        let board = rook_endgame(&compiled, Color::Black, [(0, 7), (4, 4), (4, 3)]);
+        assert_eq!(tables.probe(&board), Some(Outcome::Draw));
+
+        let [white, _] = tables.table("rook,king/king").unwrap().summary();
+        assert!(white.wins > 0);
+        assert_eq!(white.unknown, 0);
+        assert!(white.longest_mate <= 31, "{}", white.longest_mate);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let compiled = ChessemblyCompiled::from_script(CHAMELEON).unwrap();
+        let mut tables = Tablebases::new(compiled.script_hash());
+        tables.generate(&compiled, "king/king").unwrap();
+        let bytes = tables.to_bytes();
+        assert_eq!(Tablebases::from_bytes(&bytes), Ok(tables));
+        assert_eq!(Tablebases::from_bytes(&bytes[..bytes.len() - 1]), Err(TablebaseError::Truncated));
+        assert_eq!(Tablebases::from_bytes(b"CSMB"), Err(TablebaseError::BadMagic));
+        let mut old = bytes.clone();
+        old[4] = 1;
+        assert_eq!(Tablebases::from_bytes(&old), Err(TablebaseError::UnsupportedVersion(1)));
+    }
+
+    #[test]
+    fn mates_longer_than_the_table_can_hold_are_unknown() {
+        // 0은 메이트 당한 위치이고 i는 i - 1로만 갈 수 있는 긴 사슬. 마지막 위치는 수가 없는 비김입니다.
+        let count = MAX_DTM + 10;
+        let mut values = vec![UNKNOWN; count + 1];
+        values[0] = DTM_BASE;
+        values[count] = DRAW;
+        let child_start: Vec<u32> = (0..=count + 1).map(|i| i.saturating_sub(1).min(count - 1) as u32).collect();
+        let children: Vec<u32> = (0..count - 1).map(|i| i as u32).collect();
+        retrograde(&mut values, &child_start, &children, &[]);
+
+        assert_eq!(decode(values[1]), Some(Outcome::Win(1)));
+        assert_eq!(decode(values[2]), Some(Outcome::Loss(2)));
+        assert_eq!(decode(values[MAX_DTM]), Some(Outcome::Loss(MAX_DTM as u8)));
+        for value in &values[MAX_DTM + 1..count] {
+            assert_eq!(*value, UNKNOWN);
+        }
+        assert_eq!(values[count], DRAW);
+    }
+}
@@ -0,0 +1,124 @@
+use std::mem::size_of;
+
+/// 저장된 점수가 정확한 값인지, 하한/상한인지 나타냅니다.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Bound {
+    /// 창 안에서 끝난 탐색: 정확한 점수
+    Exact,
+    /// 베타 컷오프: 실제 점수는 이보다 크거나 같음
+    Lower,
+    /// 어떤 수도 알파를 넘지 못함: 실제 점수는 이보다 작거나 같음
+    Upper,
+}
+
+#[derive(Clone, Debug)]
+pub struct TtEntry<M> {
+    pub key: u64,
+    pub depth: u8,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<M>,
+}
+
+/// 슬롯 두 개짜리 버킷.
+/// 첫 슬롯은 깊이 우선(같거나 더 깊은 항목으로만 교체), 둘째 슬롯은 항상 교체합니다.
+/// 테이블은 탐색마다 새로 만들므로 이전 탐색의 항목은 없습니다.
+type Bucket<M> = [Option<TtEntry<M>>; 2];
+
+/// 위치 해시를 키로 하는 고정 크기 트랜스포지션 테이블
+pub struct TranspositionTable<M> {
+    buckets: Vec<Bucket<M>>,
+}
+
+impl<M: Clone> TranspositionTable<M> {
+    /// 약 `bytes` 바이트를 쓰는 테이블을 만듭니다.
+    /// 수가 힙에 들고 있는 데이터는 계산에 들어가지 않으므로 실제 사용량은 조금 더 큽니다.
+    pub fn with_memory(bytes: usize) -> Self {
+        let len = (bytes / size_of::<Bucket<M>>()).max(1);
+        let mut buckets = Vec::with_capacity(len);
+        buckets.resize_with(len, || [None, None]);
+        TranspositionTable { buckets }
+    }
+
+    #[inline]
+    fn index(&self, key: u64) -> usize {
+        (key % self.buckets.len() as u64) as usize
+    }
+
+    pub fn probe(&self, key: u64) -> Option<&TtEntry<M>> {
+        self.buckets[self.index(key)]
+            .iter()
+            .flatten()
+            .find(|entry| entry.key == key)
+    }
+
+    pub fn store(&mut self, key: u64, depth: u8, bound: Bound, score: i32, best_move: Option<M>) {
+        let index = self.index(key);
+        let bucket = &mut self.buckets[index];
+
+        let slot = if let Some(i) = bucket
+            .iter()
+            .position(|entry| entry.as_ref().is_some_and(|e| e.key == key))
+        {
+            i
+        } else if bucket[0]
+            .as_ref()
+            .is_none_or(|e| depth >= e.depth)
+        {
+            0
+        } else {
+            1
+        };
+
+        // 같은 위치를 더 얕게 다시 저장할 때 최선의 수를 잃지 않도록 합니다.
+        let best_move = best_move.or_else(|| {
+            bucket[slot]
+                .as_ref()
+                .filter(|e| e.key == key)
+                .and_then(|e| e.best_move.clone())
+        });
+
+        bucket[slot] = Some(TtEntry {
+            key,
+            depth,
+            bound,
+            score,
+            best_move,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 버킷이 하나뿐인 테이블: 모든 키가 같은 버킷에 들어갑니다.
+    fn one_bucket() -> TranspositionTable<u32> {
+        TranspositionTable::with_memory(1)
+    }
+
+    #[test]
+    fn deeper_entries_keep_the_first_slot() {
+        let mut tt = one_bucket();
+        tt.store(1, 5, Bound::Exact, 10, Some(1));
+        tt.store(2, 3, Bound::Lower, 20, Some(2));
+        tt.store(3, 2, Bound::Upper, 30, Some(3));
+        // 얕은 항목은 둘째 슬롯만 바꿉니다.
+        assert_eq!(tt.probe(1).map(|e| e.depth), Some(5));
+        assert!(tt.probe(2).is_none());
+        assert_eq!(tt.probe(3).map(|e| (e.bound, e.score)), Some((Bound::Upper, 30)));
+
+        tt.store(4, 6, Bound::Exact, 40, None);
+        assert!(tt.probe(1).is_none());
+        assert_eq!(tt.probe(4).map(|e| e.depth), Some(6));
+    }
+
+    #[test]
+    fn shallower_store_keeps_the_best_move() {
+        let mut tt = one_bucket();
+        tt.store(1, 5, Bound::Exact, 10, Some(7));
+        tt.store(1, 2, Bound::Upper, -5, None);
+        let entry = tt.probe(1).unwrap();
+        assert_eq!((entry.depth, entry.bound, entry.score, entry.best_move), (2, Bound::Upper, -5, Some(7)));
+    }
+}
@@ -0,0 +1,173 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::chessembly::board::{Board, PieceValues};
+use crate::chessembly::{ChessemblyCompiled, Color, Piece, PieceSpan};
+
+use super::rng::Rng;
+
+/// isolate(스레드)에 두는 스크립트별 기물 가치 표의 수. 넘으면 모두 비우고 다시 채웁니다.
+const MAX_SCRIPTS: usize = 64;
+
+thread_local! {
+    /// 스크립트 해시(`ChessemblyCompiled::script_hash`)별 기물 가치 표.
+    /// 같은 스크립트로 오는 요청마다 기동성을 다시 재지 않도록 요청보다 오래 둡니다.
+    static SCRIPT_VALUES: RefCell<HashMap<u64, PieceValues>> = RefCell::new(HashMap::new());
+}
+
+/// 기물 가치를 물어봤는데 잴 수 없을 때 쓰는 값 (센티폰)
+const UNKNOWN_VALUE: i32 = 800;
+/// 추정 가치의 범위. 움직이지 못하는 기물도 폰보다는 낫게 봅니다.
+const MIN_ESTIMATE: i32 = 100;
+const MAX_ESTIMATE: i32 = 1500;
+
+// 표본 보드: 시드를 고정해 같은 스크립트는 항상 같은 가치를 얻습니다.
+const SAMPLE_SEED: u64 = 0x5eed_ba1e;
+const SAMPLE_BOARDS: usize = 8;
+/// 표본 보드의 각 칸에 폰이 놓일 확률
+const SAMPLE_DENSITY: f64 = 0.3;
+
+// 기동성을 가치로 바꾸는 직선 (센티폰 = 기울기 * 기동성 + 절편).
+// 나이트, 비숍, 룩, 퀸의 기동성을 이 모듈과 같은 방법으로 재서 (4.9, 7.0, 11.0, 18.0)
+// 표준 가치에 최소제곱으로 맞췄습니다.
+const MOBILITY_SLOPE: i32 = 46;
+const MOBILITY_INTERCEPT: i32 = 40;
+
+/// 표준 기물의 가치 (센티폰)
+fn standard_value(piece: &str) -> Option<i32> {
+    match piece {
+        "pawn" => Some(100),
+        "knight" => Some(320),
+        "bishop" => Some(330),
+        "rook" => Some(500),
+        "queen" => Some(900),
+        // 킹은 잡히지 않으므로 양쪽에서 상쇄되고, 수 정렬에서만 의미가 있습니다.
+        "king" => Some(20000),
+        _ => None,
+    }
+}
+
+/// 보드의 스크립트에서의 기물 가치 (센티폰).
+/// `value(기물, n)` 지시문이 있으면 그 값을, 표준 기물이면 표준 가치를, 아니면 기동성으로 추정한 값을 씁니다.
+/// 구한 값은 스크립트 해시별 표에 두고, 보드는 처음 물어볼 때 그 표를 찾아 둡니다. (`Board::piece_values`)
+pub fn piece_value(board: &Board, piece: &str) -> i32 {
+    let values = board.piece_values.get_or_init(|| script_values(board.script));
+    if let Some(&value) = values.borrow().get(piece) {
+        return value;
+    }
+    let value = script_piece_value(board.script, piece);
+    values.borrow_mut().insert(piece.to_string(), value);
+    value
+}
+
+/// 캐시를 보지 않고 스크립트에서 바로 구한 기물 가치
+pub fn script_piece_value(script: &ChessemblyCompiled, piece: &str) -> i32 {
+    script
+        .value_override(piece)
+        .or_else(|| standard_value(piece))
+        .unwrap_or_else(|| estimate_value(script, piece))
+}
+
+/// 스크립트 해시로 찾은 기물 가치 표. 없으면 빈 표를 만들어 둡니다.
+fn script_values(script: &ChessemblyCompiled) -> PieceValues {
+    let hash = script.script_hash();
+    SCRIPT_VALUES.with_borrow_mut(|tables| {
+        if tables.len() >= MAX_SCRIPTS && !tables.contains_key(&hash) {
+            tables.clear();
+        }
+        tables.entry(hash).or_default().clone()
+    })
+}
+
+/// 기동성으로 기물 가치를 추정합니다.
+pub fn estimate_value(script: &ChessemblyCompiled, piece: &str) -> i32 {
+    let Some(mobility) = average_mobility(script, piece) else {
+        return UNKNOWN_VALUE;
+    };
+    ((MOBILITY_SLOPE as f64 * mobility) as i32 + MOBILITY_INTERCEPT).clamp(MIN_ESTIMATE, MAX_ESTIMATE)
+}
+
+/// 빈 보드와 폰을 흩어 놓은 표본 보드에서 백 기물 하나의 평균 수 개수.
+/// 두 평균의 평균을 반환합니다. 잴 칸이 없으면 `None`.
+pub fn average_mobility(script: &ChessemblyCompiled, piece: &str) -> Option<f64> {
+    let empty = mobility_on(&Board::empty(script), piece)?;
+
+    let mut rng = Rng::new(SAMPLE_SEED);
+    let mut total = 0.0;
+    let mut count = 0;
+    for _ in 0..SAMPLE_BOARDS {
+        let mut board = Board::empty(script);
+        // 승격 칸을 피하려고 양 끝 줄에는 폰을 두지 않습니다.
+        for row in board.board.iter_mut().take(7).skip(1) {
+            for square in row.iter_mut() {
+                if rng.next_f64() < SAMPLE_DENSITY {
+                    let color = if rng.below(2) == 0 { Color::White } else { Color::Black };
+                    *square = PieceSpan::Piece(Piece { piece_type: "pawn", color });
+                }
+            }
+        }
+        if let Some(mobility) = mobility_on(&board, piece) {
+            total += mobility;
+            count += 1;
+        }
+    }
+    let sample = if count > 0 { total / count as f64 } else { empty };
+
+    Some((empty + sample) / 2.0)
+}
+
+/// 보드의 빈 칸마다 백 기물을 하나씩 놓아 보고 평균 수 개수를 잽니다.
+fn mobility_on(base: &Board, piece: &str) -> Option<f64> {
+    let mut total = 0;
+    let mut squares = 0;
+    for y in 0..8u8 {
+        for x in 0..8u8 {
+            if base.piece_on(&(x, y)).is_some() {
+                continue;
+            }
+            let mut board = base.clone();
+            board.turn = Color::White;
+            board.board[y as usize][x as usize] = PieceSpan::Piece(Piece {
+                piece_type: piece,
+                color: Color::White,
+            });
+            total += board.script.get_moves(&mut board, &(x, y), false).len();
+            squares += 1;
+        }
+    }
+    (squares > 0).then(|| total as f64 / squares as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mobility_orders_the_standard_pieces() {
+        let compiled = ChessemblyCompiled::from_script("").unwrap();
+        let mobility = |piece| average_mobility(&compiled, piece).unwrap();
+        assert!(mobility("queen") > mobility("rook"));
+        assert!(mobility("rook") > mobility("knight"));
+        let estimate = |piece| estimate_value(&compiled, piece);
+        assert!(estimate("queen") > estimate("rook") && estimate("rook") > estimate("knight"));
+        // 직선은 표준 가치에 맞춘 것이므로 추정치도 표준 가치와 크게 다르지 않습니다.
+        assert!((estimate("rook") - 500).abs() < 100, "{}", estimate("rook"));
+    }
+
+    #[test]
+    fn value_directives_override_the_estimate() {
+        let script = "value(knight, 3.25); value(wazir, 1.5); piece(wazir) move(1, 0); piece(ferz) move(1, 1);";
+        let compiled = ChessemblyCompiled::from_script(script).unwrap();
+        let board = Board::empty(&compiled);
+        assert_eq!(piece_value(&board, "knight"), 325);
+        assert_eq!(piece_value(&board, "wazir"), 150);
+        assert_eq!(piece_value(&board, "rook"), 500);
+        assert_eq!(piece_value(&board, "ferz"), estimate_value(&compiled, "ferz"));
+
+        // 같은 스크립트로 만든 다른 보드는 같은 표를 씁니다. 다른 스크립트와는 나누지 않습니다.
+        let again = ChessemblyCompiled::from_script(script).unwrap();
+        assert!(script_values(&again).borrow().contains_key("ferz"));
+        let plain = ChessemblyCompiled::from_script("").unwrap();
+        assert_eq!(piece_value(&Board::empty(&plain), "knight"), 320);
+    }
+}
@@ -1,82 +1,675 @@
+use std::sync::LazyLock;
+
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::{any, get, post};
+use axum::Router;
+use serde::Serialize;
+use tower_service::Service;
 use worker::*;
 
-use crate::chessembly::{Board, ChessemblyCompiled};
+use crate::api::{
+    compile_script, format_script_hash, parse_body, play_history, play_move, ApiError, Backend, HealthResponse, Json,
+    LegalMovesRequest, LegalMovesResponse, Mode, MoveView, PlayRequest, PlayResponse, PositionView, Request,
+    ScriptRequest, SearchOptions, ValidateScriptResponse, REQUEST_VERSION,
+};
+use crate::chessembly::board::Board;
+use crate::chessembly::{ChessMove, ChessemblyCompiled};
+use crate::engine::analysis::analyze;
+use crate::engine::annotate::annotate_game;
+use crate::engine::game_logic::GameState;
+use crate::engine::book::OpeningBook;
+use crate::engine::eval::PositionalEvaluator;
+use crate::engine::mcts::MctsConfig;
+use crate::engine::tablebase::Tablebases;
+use crate::engine::search::{SearchBackend, SearchConfig, SearchLimits, SearchResult, SearchStats};
+use crate::engine::skill::skill_level;
+use crate::session::{Game, GameMoveRequest, GameMoveResponse, GameStore, GameView, KvGameStore, MissingGameStore};
 
+pub mod api;
 pub mod chessembly;
 pub mod engine;
+pub mod session;
 
-// fn router() -> Router {
-//     Router::new().route("/", get(root))
-// }
+/// 깊이를 지정하지 않았을 때의 최대 탐색 깊이
+const DEFAULT_MAX_DEPTH: u8 = 6;
+/// 노드 수를 지정하지 않았을 때의 노드 한도 (Worker CPU 한도 안에 들어오도록).
+/// chameleon.csm의 여러 중반 위치에서 깊이 3까지 마치는 데 5000 노드 남짓이 들었습니다.
+const DEFAULT_MAX_NODES: u64 = 6000;
+/// JSON 요청 본문의 최대 크기
+const MAX_BODY_BYTES: usize = 1 << 20;
+/// 게임 세션을 저장할 KV 네임스페이스의 바인딩 이름. 없으면 `/games` 요청은 500 `storage` 오류입니다.
+const GAMES_BINDING: &str = "GAMES";
 
-#[event(fetch)]
-async fn fetch(req: HttpRequest, _env: Env, _ctx: Context) -> Result<worker::Response> {
-    // return Ok(worker::Response::from_body(ResponseBody::Body(String::from("null").into_bytes())).unwrap());
-    let (Some(position), Some(script), Some(data)) = (
-        req.headers().get("position"),
-        req.headers().get("Chessembly"),
-        req.headers().get("Turn"),
-    ) else {
-        return Ok(Response::from_body(ResponseBody::Body(
-            "asdf".as_bytes().to_vec(),
-        ))?);
+/// Worker 번들에 넣는 오프닝 북들 (`bin/book`으로 만든 파일).
+/// 요청한 스크립트와 해시가 같은 북만 씁니다. 예: `include_bytes!("../books/standard.book")`
+const BOOKS: &[&[u8]] = &[];
+
+/// `BOOKS`를 읽은 것. isolate마다 처음 쓸 때 한 번만 읽습니다. 읽지 못한 파일은 건너뜁니다.
+static PARSED_BOOKS: LazyLock<Vec<OpeningBook>> =
+    LazyLock::new(|| BOOKS.iter().filter_map(|bytes| OpeningBook::from_bytes(bytes).ok()).collect());
+
+/// 이 스크립트로 만든 오프닝 북. 여러 개면 처음 것을 씁니다.
+fn book_for(compiled: &ChessemblyCompiled) -> Option<&'static OpeningBook> {
+    let script_hash = compiled.script_hash();
+    PARSED_BOOKS.iter().find(|book| book.script_hash == script_hash)
+}
+
+/// Worker 번들에 넣는 엔드게임 테이블들 (`bin/tablebase`로 만든 파일). 오프닝 북처럼 스크립트 해시로 고릅니다.
+const TABLEBASES: &[&[u8]] = &[];
+
+static PARSED_TABLEBASES: LazyLock<Vec<Tablebases>> =
+    LazyLock::new(|| TABLEBASES.iter().filter_map(|bytes| Tablebases::from_bytes(bytes).ok()).collect());
+
+fn tablebases_for(compiled: &ChessemblyCompiled) -> Option<&'static Tablebases> {
+    let script_hash = compiled.script_hash();
+    PARSED_TABLEBASES.iter().find(|tables| tables.script_hash == script_hash)
+}
+
+/// Worker 번들에 넣는 평가 가중치들 (`bin/tune`으로 만든 JSON). 가중치 파일에는 스크립트 해시가 없으므로
+/// `bin/tune`이 저장할 때 알려 주는 `ChessemblyCompiled::script_hash`와 쌍으로 적습니다.
+/// 없는 스크립트는 기본 가중치로 평가합니다. 예: `(0x0123_4567_89ab_cdef, include_str!("../weights/chameleon.json"))`
+const EVALUATORS: &[(u64, &str)] = &[];
+
+/// `EVALUATORS`를 읽은 것. 읽지 못한 가중치 파일은 건너뜁니다.
+static PARSED_EVALUATORS: LazyLock<Vec<(u64, PositionalEvaluator)>> = LazyLock::new(|| {
+    EVALUATORS
+        .iter()
+        .filter_map(|&(hash, json)| Some((hash, PositionalEvaluator::from_json(json).ok()?)))
+        .collect()
+});
+
+fn evaluator_for(compiled: &ChessemblyCompiled) -> Option<&'static PositionalEvaluator> {
+    let script_hash = compiled.script_hash();
+    PARSED_EVALUATORS
+        .iter()
+        .find(|(hash, _)| *hash == script_hash)
+        .map(|(_, evaluator)| evaluator)
+}
+
+/// 요청에 시드가 없을 때 쓸 시드. JS 숫자로 정확히 돌려줄 수 있도록 53비트 안에서 만듭니다.
+fn random_seed() -> u64 {
+    (js_sys::Math::random() * (1u64 << 53) as f64) as u64
+}
+
+#[derive(Serialize)]
+struct BestMoveResponse<'r, M> {
+    #[serde(flatten)]
+    result: &'r SearchResult<M>,
+    /// 이 탐색에 쓴 시드. 같은 요청에 이 값을 `seed` 옵션으로 보내면 같은 결과가 나옵니다.
+    seed: u64,
+    /// `debug` 옵션을 켰을 때만 넣는 탐색 통계
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<&'r SearchStats>,
+}
+
+/// 함께 쓸 수 없는 옵션을 거절합니다.
+/// MCTS는 평가 잡음, `root_variety`, 실수를 쓰지 않으므로 난이도 중 노드 한도만 적용될 것이기 때문입니다.
+fn check_options(options: &SearchOptions) -> std::result::Result<(), ApiError> {
+    if options.level.is_some() && options.backend != Backend::Alphabeta {
+        return Err(ApiError::UnsupportedOptions {
+            detail: "level은 alphabeta 백엔드에서만 쓸 수 있습니다".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// 요청의 탐색 옵션과 스크립트에 맞는 북, 테이블, 평가 가중치로 만든 탐색 설정
+fn search_config<'c>(
+    options: &SearchOptions,
+    seed: u64,
+    compiled: &ChessemblyCompiled,
+) -> std::result::Result<SearchConfig<'c>, ApiError> {
+    check_options(options)?;
+    // movetime은 하드 한도로 쓰고, 절반이 지나면 새 반복을 시작하지 않습니다.
+    let movetime = options.movetime;
+    let mut config = SearchConfig {
+        limits: SearchLimits {
+            max_depth: options.depth.unwrap_or(DEFAULT_MAX_DEPTH),
+            max_nodes: Some(options.nodes.unwrap_or(DEFAULT_MAX_NODES)),
+            soft_deadline: movetime.map(|t| t / 2.0),
+            hard_deadline: movetime,
+            ..SearchLimits::default()
+        },
+        multi_pv: options.multi_pv.unwrap_or(1),
+        seed,
+        root_variety: options.variety,
+        backend: match options.backend {
+            Backend::Alphabeta => SearchBackend::AlphaBeta,
+            Backend::Mcts => SearchBackend::Mcts(MctsConfig::default()),
+            Backend::MctsHeavy => SearchBackend::Mcts(MctsConfig {
+                heavy_playouts: true,
+                ..MctsConfig::default()
+            }),
+        },
+        book: match options.book {
+            Some(false) => None,
+            _ => book_for(compiled),
+        },
+        tablebases: tablebases_for(compiled),
+        evaluator: evaluator_for(compiled),
+        profile: options.debug,
+        ..SearchConfig::default()
     };
+    if let Some(skill) = options.level.and_then(skill_level) {
+        skill.apply(&mut config);
+    }
+    Ok(config)
+}
+
+/// 새 게임의 id. id만 알면 게임에 수를 둘 수 있으므로 암호학적 난수 128비트를 16진수로 적습니다.
+/// (Worker에서는 `crypto.getRandomValues`)
+fn new_game_id() -> std::result::Result<String, ApiError> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| ApiError::Internal { detail: e.to_string() })?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
 
-    let Ok(str_script) = script.to_str() else {
-        return Ok(Response::from_body(ResponseBody::Body(
-            "asdf".as_bytes().to_vec(),
-        ))?);
+fn router<G: GameStore>(store: G) -> Router {
+    Router::new()
+        // 예전 클라이언트는 경로 없이 헤더로 보냅니다.
+        .route("/", any(bestmove))
+        .route("/bestmove", post(bestmove))
+        .route("/legal-moves", post(legal_moves))
+        .route("/play", post(play))
+        .route("/validate-script", post(validate_script))
+        .route("/health", get(health))
+        .route("/games", post(create_game::<G>))
+        .route("/games/{id}", get(get_game::<G>))
+        .route("/games/{id}/moves", post(game_move::<G>))
+        .route("/games/{id}/bot-move", post(bot_move::<G>))
+        .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+        .with_state(store)
+}
+
+#[event(fetch)]
+async fn fetch(req: HttpRequest, env: Env, _ctx: Context) -> Result<axum::http::Response<axum::body::Body>> {
+    let response = match env.kv(GAMES_BINDING) {
+        Ok(kv) => router(KvGameStore::new(kv)).call(req).await?,
+        Err(e) => {
+            console_error!("{GAMES_BINDING} KV 바인딩을 찾지 못해 게임 요청을 거절합니다: {e}");
+            router(MissingGameStore { binding: GAMES_BINDING }).call(req).await?
+        }
     };
+    Ok(response)
+}
 
-    let Ok(compiled) = ChessemblyCompiled::from_script(str_script) else {
-        return Ok(Response::from_body(ResponseBody::Body(
-            "asdf".as_bytes().to_vec(),
-        ))?);
+/// 수 하나를 고르거나(`mode`에 따라) 모든 수를 분석하거나 대국을 주석합니다.
+async fn bestmove(headers: HeaderMap, body: Bytes) -> std::result::Result<axum::response::Response, ApiError> {
+    // 예전 클라이언트는 헤더로 보내고, 새 클라이언트는 JSON 본문으로 보냅니다.
+    let (request, legacy) = match Request::from_headers(&headers)? {
+        Some(request) => (request, true),
+        None => (Request::from_json(&body)?, false),
     };
 
-    console_log!("{:?}", compiled.chains);
-    let mut board = Board::empty(&compiled);
-    let mut i = 0;
-    for line in position.to_str().unwrap().split('/') {
-        let mut j = 0;
-        for pc in line.split_whitespace() {
-            if let Some((piece_name, color)) = pc.split_once(':') {
-                board.board[i][j] = chessembly::PieceSpan::Piece(chessembly::Piece {
-                    piece_type: piece_name,
-                    color: if color == "white" {
-                        chessembly::Color::White
-                    } else {
-                        chessembly::Color::Black
-                    },
-                });
-            }
-            j += 1;
-        }
-        i += 1;
+    let script = request.compile()?;
+    let compiled = script.compiled();
+    let mut board = request.board(compiled)?;
+
+    let options = &request.options;
+    let seed = options.seed.unwrap_or_else(random_seed);
+    let config = search_config(options, seed, compiled)?;
+
+    // annotate: 이 위치부터 history의 수를 두며 대국을 주석합니다.
+    if options.mode == Mode::Annotate {
+        let moves: Vec<&str> = request.history.iter().map(String::as_str).collect();
+        return Ok(Json(annotate_game(&board, &moves, &config)?).into_response());
+    }
+
+    play_history(&mut board, &request.history)?;
+    if board.is_terminal() {
+        return Err(ApiError::GameOver {
+            ply: request.history.len(),
+            status: board.status(),
+        });
     }
-    board.turn = if data.to_str().unwrap() == "white" {
-        chessembly::Color::White
-    } else {
-        chessembly::Color::Black
+
+    // analyze: 수 하나를 고르는 대신 모든 합법적인 수의 점수와 분류를 돌려줍니다.
+    if options.mode == Mode::Analyze {
+        let analysis = analyze(&mut board, config).map_err(|code| ApiError::SearchFailed { code })?;
+        return Ok(Json(analysis).into_response());
+    }
+
+    let node = engine::search::find_best_move(&mut board, &config).map_err(|code| ApiError::SearchFailed { code })?;
+    // 헤더로 보낸 예전 클라이언트는 예전처럼 `[수, 점수]` 배열을 받습니다. 주 변화와 통계는 JSON 본문으로 보내야 받습니다.
+    if legacy {
+        return Ok(Json((&node.best_move, node.score)).into_response());
+    }
+    Ok(Json(BestMoveResponse {
+        result: &node,
+        seed,
+        stats: options.debug.then_some(&node.stats),
+    })
+    .into_response())
+}
+
+/// `history`까지 둔 위치의 합법적인 수와 양쪽이 공격하는 칸. `square`가 있으면 그 칸의 기물의 수만.
+async fn legal_moves(body: Bytes) -> std::result::Result<axum::response::Response, ApiError> {
+    let request: LegalMovesRequest = parse_body(&body)?;
+    request.game.check_version()?;
+    let script = request.game.compile()?;
+    let mut board = request.game.current_board(script.compiled())?;
+    Ok(Json(LegalMovesResponse::new(&mut board, request.square)?).into_response())
+}
+
+/// `history`까지 둔 위치에서 수 하나를 두고, 새 위치와 상태를 돌려줍니다.
+async fn play(body: Bytes) -> std::result::Result<axum::response::Response, ApiError> {
+    let request: PlayRequest = parse_body(&body)?;
+    request.game.check_version()?;
+    let script = request.game.compile()?;
+    let mut board = request.game.current_board(script.compiled())?;
+    let m = play_move(&mut board, &request.mv, request.game.history.len())?;
+    Ok(Json(PlayResponse {
+        mv: MoveView::from(m),
+        position: PositionView::new(&mut board),
+    })
+    .into_response())
+}
+
+/// 스크립트를 확인합니다. 올바르면 캐시에 넣고, 다음 요청에서 `script` 대신 보낼 수 있는 해시를 돌려줍니다.
+async fn validate_script(body: Bytes) -> std::result::Result<axum::response::Response, ApiError> {
+    let request: ScriptRequest = parse_body(&body)?;
+    let response = match compile_script(&request.script) {
+        Ok(script) => ValidateScriptResponse {
+            valid: true,
+            diagnostics: Vec::new(),
+            script_hash: Some(format_script_hash(script.hash())),
+        },
+        Err(ApiError::InvalidScript { diagnostics }) => ValidateScriptResponse {
+            valid: false,
+            diagnostics,
+            script_hash: None,
+        },
+        Err(e) => return Err(e),
     };
+    Ok(Json(response).into_response())
+}
 
-    // worker::console_log!("{}", board.to_string());
+async fn health() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok",
+        version: env!("CARGO_PKG_VERSION"),
+        request_version: REQUEST_VERSION,
+    })
+}
 
-    let best_move = engine::search::find_best_move(&mut board, 3);
-    if let Ok(node) = best_move {
-        return Ok(Response::from_json(&node)?);
-    } else if let Err(n) = best_move {
-        console_log!("????? {}", n);
-        return Ok(Response::from_body(ResponseBody::Body(
-            String::from("null").into_bytes(),
-        ))?);
+/// 게임을 만듭니다. 본문은 `/bestmove`와 같은 `Request`이고, `options`는 봇이 둘 때 씁니다.
+async fn create_game<G: GameStore>(
+    State(store): State<G>,
+    body: Bytes,
+) -> std::result::Result<axum::response::Response, ApiError> {
+    let mut setup = Request::from_json(&body)?;
+    check_options(&setup.options)?;
+    // 해시로 보낸 스크립트도 다른 isolate에서 다시 컴파일할 수 있도록 소스로 저장합니다.
+    if setup.script.is_empty() {
+        setup.script = setup.compile()?.source().to_string();
+        setup.script_hash = None;
     }
-    // println!("{:?}", req.body());
-    Ok(Response::from_body(ResponseBody::Body(
-        "asdf".as_bytes().to_vec(),
-    ))?)
+    let game = Game { id: new_game_id()?, setup };
+    let response = game_view(&game)?;
+    store.save(&game, None).await?;
+    Ok((StatusCode::CREATED, response).into_response())
+}
+
+/// 게임의 기보(`history`)와 지금 위치
+async fn get_game<G: GameStore>(
+    State(store): State<G>,
+    Path(id): Path<String>,
+) -> std::result::Result<axum::response::Response, ApiError> {
+    let game = load_game(&store, id).await?;
+    game_view(&game)
 }
 
-pub async fn root() -> &'static str {
-    "Hello Axum!"
+/// 게임에 수 하나를 둡니다.
+async fn game_move<G: GameStore>(
+    State(store): State<G>,
+    Path(id): Path<String>,
+    body: Bytes,
+) -> std::result::Result<axum::response::Response, ApiError> {
+    let request: GameMoveRequest = parse_body(&body)?;
+    let mut game = load_game(&store, id).await?;
+    let ply = game.ply();
+    let response = play_game_move(&mut game, &request.mv)?;
+    store.save(&game, Some(ply)).await?;
+    Ok(response)
+}
+
+/// 게임을 만들 때의 `options`로 탐색해 봇이 둡니다.
+async fn bot_move<G: GameStore>(
+    State(store): State<G>,
+    Path(id): Path<String>,
+) -> std::result::Result<axum::response::Response, ApiError> {
+    let mut game = load_game(&store, id).await?;
+    let ply = game.ply();
+    let response = play_bot_move(&mut game)?;
+    store.save(&game, Some(ply)).await?;
+    Ok(response)
+}
+
+async fn load_game<G: GameStore>(store: &G, id: String) -> std::result::Result<Game, ApiError> {
+    store.load(&id).await?.ok_or(ApiError::GameNotFound { id })
+}
+
+// 보드는 `Send`가 아니므로, 저장소를 기다리는 사이에 들고 있지 않도록 아래 함수들에서만 만듭니다.
+
+fn game_view(game: &Game) -> std::result::Result<axum::response::Response, ApiError> {
+    let script = game.setup.compile()?;
+    let mut board = game.setup.current_board(script.compiled())?;
+    Ok(Json(GameView::new(game, &mut board)).into_response())
+}
+
+fn play_game_move(game: &mut Game, text: &str) -> std::result::Result<axum::response::Response, ApiError> {
+    // 보드가 스크립트를 빌리는 동안 `history`에 수를 더하므로 복사해 둡니다.
+    let setup = game.setup.clone();
+    let script = setup.compile()?;
+    let mut board = setup.current_board(script.compiled())?;
+    let m = play_move(&mut board, text, game.ply())?;
+    game_move_response(game, &mut board, m, None)
+}
+
+fn play_bot_move(game: &mut Game) -> std::result::Result<axum::response::Response, ApiError> {
+    let setup = game.setup.clone();
+    let script = setup.compile()?;
+    let compiled = script.compiled();
+    let mut board = setup.current_board(compiled)?;
+    if board.is_terminal() {
+        return Err(ApiError::GameOver {
+            ply: game.ply(),
+            status: board.status(),
+        });
+    }
+    let options = &setup.options;
+    let config = search_config(options, options.seed.unwrap_or_else(random_seed), compiled)?;
+    let node = engine::search::find_best_move(&mut board, &config).map_err(|code| ApiError::SearchFailed { code })?;
+    let mut board = board.make_move_new(&node.best_move);
+    game_move_response(game, &mut board, node.best_move, Some(node.score))
+}
+
+/// 둔 수를 `history`에 더하고 응답을 만듭니다.
+/// 짧은 표기나 보낸 글이 아니라 전체 표기를 넣으므로, 잡는 칸이나 변신만 다른 수도 기보에서 구별됩니다.
+fn game_move_response(
+    game: &mut Game,
+    board: &mut Board,
+    m: ChessMove,
+    score: Option<i32>,
+) -> std::result::Result<axum::response::Response, ApiError> {
+    game.setup.history.push(m.notation());
+    Ok(Json(GameMoveResponse {
+        mv: MoveView::from(m),
+        score,
+        game: GameView::new(game, board),
+    })
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::session::{ready, MemoryGameStore};
+
+    const START: &str = "rook:black knight:black bishop:black queen:black king:black bishop:black knight:black rook:black/\
+        pawn:black pawn:black pawn:black pawn:black pawn:black pawn:black pawn:black pawn:black/./././.\
+        /pawn:white pawn:white pawn:white pawn:white pawn:white pawn:white pawn:white pawn:white\
+        /rook:white knight:white bishop:white queen:white king:white bishop:white knight:white rook:white";
+
+    /// 요청 하나를 보내고 상태 코드와 JSON 본문을 돌려줍니다.
+    fn send<G: GameStore>(store: &G, method: &str, uri: &str, body: Option<Value>) -> (u16, Value) {
+        let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+        call(store, axum::http::Request::builder().method(method).uri(uri).body(body).unwrap())
+    }
+
+    fn call<G: GameStore>(store: &G, request: axum::http::Request<Body>) -> (u16, Value) {
+        let response = ready(router(store.clone()).call(request)).unwrap();
+        let status = response.status().as_u16();
+        let bytes = ready(axum::body::to_bytes(response.into_body(), usize::MAX)).unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    /// 카멜레온 스크립트의 시작 위치 요청에 `extra`의 항목을 더한 본문
+    fn start_request(extra: Value) -> Value {
+        let mut body = json!({
+            "script": include_str!("../chameleon.csm"),
+            "position": START,
+            "turn": "white",
+        });
+        body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        body
+    }
+
+    /// 응답의 수들의 좌표 표기를 정렬한 것
+    fn notations(moves: &Value) -> Vec<String> {
+        let mut notations: Vec<String> = moves
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["notation"].as_str().unwrap().to_string())
+            .collect();
+        notations.sort();
+        notations
+    }
+
+    #[test]
+    fn bestmove_returns_a_legal_move_and_echoes_the_seed() {
+        let store = MemoryGameStore::default();
+        let request = start_request(json!({ "options": { "depth": 2, "seed": 7, "book": false, "debug": true } }));
+        let (status, body) = send(&store, "POST", "/bestmove", Some(request.clone()));
+        assert_eq!(status, 200);
+        assert_eq!(body["seed"], 7);
+        assert_eq!(body["depth"], 2);
+        assert!(body["stats"]["nodes"].as_u64().unwrap() > 0, "{body}");
+        let (_, legal) = send(&store, "POST", "/legal-moves", Some(start_request(json!({}))));
+        // `/legal-moves`의 수는 같은 필드에 좌표 표기를 더한 것입니다.
+        let legal: Vec<Value> = legal["moves"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| {
+                let mut m = m.clone();
+                m.as_object_mut().unwrap().remove("notation");
+                m
+            })
+            .collect();
+        assert!(legal.contains(&body["move"]), "{}", body["move"]);
+        assert_eq!(body["pv"][0], body["move"]);
+
+        // 같은 시드면 같은 수입니다.
+        let (_, again) = send(&store, "POST", "/bestmove", Some(request));
+        assert_eq!(again["move"], body["move"]);
+
+        let (status, error) = send(&store, "POST", "/bestmove", Some(json!({ "position": START })));
+        assert_eq!((status, &error["error"]), (400, &json!("missing-field")));
+    }
+
+    #[test]
+    fn levels_are_rejected_with_mcts() {
+        let store = MemoryGameStore::default();
+        for backend in ["mcts", "mcts-heavy"] {
+            let request = start_request(json!({ "options": { "level": 3, "backend": backend, "seed": 1 } }));
+            let (status, error) = send(&store, "POST", "/bestmove", Some(request.clone()));
+            assert_eq!((status, &error["error"]), (422, &json!("unsupported-options")));
+            // 봇이 둘 때가 아니라 게임을 만들 때 거절합니다.
+            let (status, _) = send(&store, "POST", "/games", Some(request));
+            assert_eq!(status, 422);
+        }
+        let request = start_request(json!({ "options": { "level": 3, "seed": 1 } }));
+        let (status, _) = send(&store, "POST", "/bestmove", Some(request));
+        assert_eq!(status, 200);
+    }
+
+    #[test]
+    fn legal_moves_filter_by_square_and_show_attacked_squares() {
+        let store = MemoryGameStore::default();
+        let (status, all) = send(&store, "POST", "/legal-moves", Some(start_request(json!({}))));
+        assert_eq!(status, 200);
+        assert_eq!((&all["turn"], &all["legal"], &all["check"]), (&json!("white"), &json!(true), &json!(false)));
+        assert_eq!(all["moves"].as_array().unwrap().len(), 20);
+
+        // 자기 기물의 칸이면 그 기물의 합법적인 수만 돌려줍니다.
+        let (_, knight) = send(&store, "POST", "/legal-moves", Some(start_request(json!({ "square": [6, 7] }))));
+        assert_eq!(knight["legal"], true);
+        assert_eq!(notations(&knight["moves"]), ["g1f3", "g1h3"]);
+
+        // 상대 기물의 칸이면 그 기물이 갈 수 있는 칸을 체크 확인 없이 보여 줍니다.
+        let (_, opponent) = send(&store, "POST", "/legal-moves", Some(start_request(json!({ "square": [1, 0] }))));
+        assert_eq!(opponent["legal"], false);
+        assert_eq!(notations(&opponent["moves"]), ["b8a6", "b8c6"]);
+
+        let (_, empty) = send(&store, "POST", "/legal-moves", Some(start_request(json!({ "square": [4, 4] }))));
+        assert_eq!(empty["moves"], json!([]));
+
+        // 양쪽이 공격하는 칸: 수가 닿는 칸이므로 백은 f3과 d4까지, 흑은 f6과 d5까지입니다.
+        let attacked = |color: &str, square: Value| all["attacked"][color].as_array().unwrap().contains(&square);
+        assert!(attacked("white", json!([5, 5])) && attacked("white", json!([3, 4])));
+        assert!(attacked("black", json!([5, 2])) && attacked("black", json!([3, 3])));
+        assert!(!attacked("white", json!([3, 3])) && !attacked("black", json!([3, 4])));
+
+        // 기보를 둔 뒤의 위치에서 봅니다.
+        let (_, reply) = send(&store, "POST", "/legal-moves", Some(start_request(json!({ "history": ["e2e4"], "square": [4, 1] }))));
+        assert_eq!((&reply["turn"], &reply["legal"]), (&json!("black"), &json!(true)));
+        assert_eq!(notations(&reply["moves"]), ["e7e5", "e7e6"]);
+
+        let (status, error) = send(&store, "POST", "/legal-moves", Some(start_request(json!({ "square": [8, 0] }))));
+        assert_eq!((status, &error["error"]), (400, &json!("bad-request")));
+    }
+
+    #[test]
+    fn play_applies_a_move_and_rejects_illegal_ones() {
+        let store = MemoryGameStore::default();
+        let (status, played) = send(&store, "POST", "/play", Some(start_request(json!({ "move": "e2e4" }))));
+        assert_eq!(status, 200);
+        assert_eq!(played["move"]["notation"], "e2e4");
+        assert_eq!((&played["turn"], &played["status"], &played["check"]), (&json!("black"), &json!("ongoing"), &json!(false)));
+        // 돌려준 위치는 그대로 다음 요청에 쓸 수 있습니다.
+        let next = json!({
+            "script": include_str!("../chameleon.csm"),
+            "position": played["position"],
+            "turn": played["turn"],
+            "state": played["state"],
+            "move": "e7e5",
+        });
+        let (status, reply) = send(&store, "POST", "/play", Some(next));
+        assert_eq!((status, &reply["turn"]), (200, &json!("white")));
+
+        let (status, error) = send(&store, "POST", "/play", Some(start_request(json!({ "history": ["e2e4"], "move": "e2e4" }))));
+        assert_eq!((status, &error["error"], &error["ply"]), (422, &json!("illegal-move"), &json!(1)));
+    }
+
+    #[test]
+    fn validated_scripts_can_be_sent_by_hash() {
+        let store = MemoryGameStore::default();
+        let script = json!({ "script": include_str!("../chameleon.csm") });
+        let (status, valid) = send(&store, "POST", "/validate-script", Some(script));
+        assert_eq!(status, 200);
+        assert_eq!((&valid["valid"], &valid["diagnostics"]), (&json!(true), &json!([])));
+        let hash = valid["script_hash"].as_str().unwrap();
+        assert_eq!(hash.len(), 32);
+
+        let by_hash = json!({ "script_hash": hash, "position": START, "turn": "white", "square": [6, 7] });
+        let (status, knight) = send(&store, "POST", "/legal-moves", Some(by_hash));
+        assert_eq!(status, 200);
+        assert_eq!(notations(&knight["moves"]), ["g1f3", "g1h3"]);
+
+        let (status, invalid) = send(&store, "POST", "/validate-script", Some(json!({ "script": "piece(rook) take-move(1, 0" })));
+        assert_eq!(status, 200);
+        assert_eq!(invalid["valid"], false);
+        assert!(!invalid["diagnostics"].as_array().unwrap().is_empty());
+        assert!(invalid.get("script_hash").is_none());
+    }
+
+    #[test]
+    fn health_reports_versions() {
+        let (status, health) = send(&MemoryGameStore::default(), "GET", "/health", None);
+        assert_eq!(status, 200);
+        assert_eq!(health["status"], "ok");
+        assert_eq!(health["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(health["request_version"], REQUEST_VERSION);
+    }
+
+    #[test]
+    fn game_session_round_trip() {
+        let store = MemoryGameStore::default();
+        let setup = json!({
+            "script": include_str!("../chameleon.csm"),
+            "position": START,
+            "turn": "white",
+            "options": { "depth": 1, "nodes": 200, "seed": 1, "book": false },
+        });
+        let (status, created) = send(&store, "POST", "/games", Some(setup));
+        assert_eq!(status, 201);
+        let id = created["id"].as_str().unwrap().to_string();
+        assert_eq!(id.len(), 32);
+        assert_eq!(created["history"], json!([]));
+
+        let (status, moved) = send(&store, "POST", &format!("/games/{id}/moves"), Some(json!({ "move": "e2e4" })));
+        assert_eq!(status, 200);
+        assert_eq!(moved["move"]["notation"], "e2e4");
+        assert_eq!(moved["history"], json!(["e2e4"]));
+
+        let (status, error) = send(&store, "POST", &format!("/games/{id}/moves"), Some(json!({ "move": "e2e4" })));
+        assert_eq!(status, 422);
+        assert_eq!(error["error"], "illegal-move");
+        assert_eq!(error["ply"], 1);
+
+        let (status, bot) = send(&store, "POST", &format!("/games/{id}/bot-move"), None);
+        assert_eq!(status, 200);
+        let reply = bot["move"]["notation"].as_str().unwrap().to_string();
+        assert_eq!(bot["history"], json!(["e2e4", reply]));
+
+        let (status, game) = send(&store, "GET", &format!("/games/{id}"), None);
+        assert_eq!(status, 200);
+        assert_eq!(game["history"], json!(["e2e4", reply]));
+        assert_eq!(game["turn"], "white");
+
+        let (status, error) = send(&store, "GET", "/games/nope", None);
+        assert_eq!((status, &error["error"]), (404, &json!("game-not-found")));
+    }
+
+    #[test]
+    fn games_fail_loudly_without_a_store_binding() {
+        let store = MissingGameStore { binding: GAMES_BINDING };
+        let (status, error) = send(&store, "POST", "/games", Some(start_request(json!({}))));
+        assert_eq!((status, &error["error"]), (500, &json!("storage")));
+        let (status, _) = send(&store, "GET", "/games/0123", None);
+        assert_eq!(status, 500);
+        // 게임이 아닌 요청은 그대로 됩니다.
+        let (status, _) = send(&store, "GET", "/health", None);
+        assert_eq!(status, 200);
+    }
+
+    #[test]
+    fn legacy_header_requests_get_a_move_and_score_pair() {
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .uri("/")
+            .header("position", START)
+            .header("Chessembly", "piece(rook) take-move(1, 0) repeat(1);")
+            .header("Turn", "white")
+            .header("Depth", "1")
+            .header("Seed", "1")
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = call(&MemoryGameStore::default(), request);
+        assert_eq!(status, 200);
+        let [mv, score] = body.as_array().unwrap().as_slice() else {
+            panic!("{body}");
+        };
+        assert!(mv["from"].is_array(), "{mv}");
+        assert!(score.is_i64());
+
+        // 같은 요청을 JSON 본문으로 보내면 주 변화가 있는 객체를 받습니다.
+        let body = json!({
+            "script": "piece(rook) take-move(1, 0) repeat(1);",
+            "position": START,
+            "turn": "white",
+            "options": { "depth": 1, "seed": 1 },
+        });
+        let (status, body) = send(&MemoryGameStore::default(), "POST", "/bestmove", Some(body));
+        assert_eq!(status, 200);
+        assert!(body["pv"].is_array() && body["lines"].is_array(), "{body}");
+    }
 }
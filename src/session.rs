@@ -0,0 +1,284 @@
+//! 게임 세션: 스크립트와 시작 위치로 게임을 만들고, 수를 두고, 봇이 두게 하고, 기보를 가져옵니다.
+//!
+//! 게임은 만든 요청(`api::Request`)에 지금까지 둔 수를 `history`로 쌓아 저장하고,
+//! 요청마다 `Request::current_board`로 다시 두어 위치를 만듭니다. 그래서 저장하는 것은 JSON 하나뿐이고,
+//! 수를 더하기 전에 언제나 그 위치의 규칙으로 확인합니다.
+//!
+//! 저장소는 `GameStore`로 바꿔 끼웁니다. `MemoryGameStore`는 isolate 하나의 메모리에 두는 저장소로
+//! 테스트용이고, `KvGameStore`는 Workers KV에 둡니다. KV 바인딩이 없으면 `MissingGameStore`가
+//! 게임 요청을 거절합니다.
+//!
+//! 저장할 때는 불러올 때의 수(`Game::ply`)를 함께 넘깁니다. 그 사이에 다른 요청이 먼저 수를 두었으면
+//! `StoreError::Conflict`로 거절합니다. `MemoryGameStore`에서는 확인과 쓰기가 한 번에 일어나지만,
+//! `KvGameStore`에서는 그렇지 않으므로 같은 게임에 동시에 보낸 수 중 하나가 사라질 수 있습니다. (`KvGameStore` 참고)
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use worker::send::{SendFuture, SendWrapper};
+use worker::KvStore;
+
+use crate::api::{ApiError, MoveView, PositionView, Request};
+use crate::chessembly::board::Board;
+
+/// KV에 저장한 게임이 마지막으로 쓴 뒤 사라지기까지의 시간 (초)
+const GAME_TTL_SECS: u64 = 60 * 60 * 24 * 30;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Game {
+    pub id: String,
+    /// 게임을 만든 요청. `history`에 둔 수가 쌓이고, `options`는 봇이 둘 때 씁니다.
+    pub setup: Request,
+}
+
+impl Game {
+    /// 다음에 둘 수의 번호 (0부터)
+    pub fn ply(&self) -> usize {
+        self.setup.history.len()
+    }
+}
+
+/// 게임의 지금 상태
+#[derive(Clone, Debug, Serialize)]
+pub struct GameView {
+    pub id: String,
+    /// 시작 위치부터 둔 모든 수 (`ChessMove::notation`). 합법적인 수마다 표기가 다르므로 다시 두면 같은 수입니다.
+    pub history: Vec<String>,
+    #[serde(flatten)]
+    pub position: PositionView,
+}
+
+impl GameView {
+    pub fn new(game: &Game, board: &mut Board) -> GameView {
+        GameView {
+            id: game.id.clone(),
+            history: game.setup.history.clone(),
+            position: PositionView::new(board),
+        }
+    }
+}
+
+/// 게임에 수 하나를 둔 결과
+#[derive(Clone, Debug, Serialize)]
+pub struct GameMoveResponse<'a> {
+    #[serde(rename = "move")]
+    pub mv: MoveView<'a>,
+    /// 봇이 둔 수면 탐색 점수 (둔 쪽의 관점, 센티폰)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<i32>,
+    #[serde(flatten)]
+    pub game: GameView,
+}
+
+/// `/games/{id}/moves` 요청
+#[derive(Clone, Debug, Deserialize)]
+pub struct GameMoveRequest {
+    #[serde(rename = "move")]
+    pub mv: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoreError {
+    /// 불러온 뒤에 다른 요청이 이 게임을 먼저 바꿨습니다. 저장된 게임은 지금 `ply`수까지 두었습니다.
+    /// 새 게임이면(`ply`가 `None`) 같은 id의 게임이 이미 있습니다.
+    Conflict { id: String, ply: Option<usize> },
+    /// 저장소를 읽거나 쓰지 못했습니다.
+    Backend(String),
+}
+
+impl StoreError {
+    fn backend(error: impl fmt::Display) -> StoreError {
+        StoreError::Backend(error.to_string())
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Conflict { id, .. } => write!(f, "게임 {id}이(가) 그사이 바뀌었습니다"),
+            StoreError::Backend(detail) => f.write_str(detail),
+        }
+    }
+}
+
+impl From<StoreError> for ApiError {
+    fn from(error: StoreError) -> Self {
+        match error {
+            StoreError::Conflict { id, ply } => ApiError::GameConflict { id, ply },
+            StoreError::Backend(detail) => ApiError::Storage { detail },
+        }
+    }
+}
+
+/// 저장된 게임이 `expected_ply`수까지 둔 그 게임인지 확인합니다. `None`이면 게임이 없어야 합니다.
+fn check_ply(id: &str, stored: Option<&Game>, expected_ply: Option<usize>) -> Result<(), StoreError> {
+    let ply = stored.map(Game::ply);
+    if ply == expected_ply {
+        Ok(())
+    } else {
+        Err(StoreError::Conflict { id: id.to_string(), ply })
+    }
+}
+
+/// 게임을 저장하는 곳. axum 라우터의 상태로 쓰므로 `Send`여야 합니다.
+pub trait GameStore: Clone + Send + Sync + 'static {
+    fn load(&self, id: &str) -> impl Future<Output = Result<Option<Game>, StoreError>> + Send;
+
+    /// 게임을 저장합니다. `expected_ply`는 불러올 때의 `Game::ply`이고, 새 게임이면 `None`입니다.
+    /// 저장된 게임이 그와 다르면 덮어쓰지 않고 `StoreError::Conflict`를 돌려줍니다.
+    fn save(&self, game: &Game, expected_ply: Option<usize>) -> impl Future<Output = Result<(), StoreError>> + Send;
+}
+
+/// 메모리에 두는 저장소. Worker에서는 isolate마다 따로 있고, isolate가 내려가면 사라집니다.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryGameStore {
+    games: Arc<Mutex<HashMap<String, Game>>>,
+}
+
+impl GameStore for MemoryGameStore {
+    async fn load(&self, id: &str) -> Result<Option<Game>, StoreError> {
+        let games = self.games.lock().map_err(StoreError::backend)?;
+        Ok(games.get(id).cloned())
+    }
+
+    async fn save(&self, game: &Game, expected_ply: Option<usize>) -> Result<(), StoreError> {
+        // 확인과 쓰기를 한 잠금 안에서 합니다.
+        let mut games = self.games.lock().map_err(StoreError::backend)?;
+        check_ply(&game.id, games.get(&game.id), expected_ply)?;
+        games.insert(game.id.clone(), game.clone());
+        Ok(())
+    }
+}
+
+/// Workers KV에 `game:{id}` 키로 두는 저장소.
+///
+/// KV에는 조건부 쓰기가 없으므로 `save`는 다시 읽고(`check_ply`로 확인하고) 쓰는 세 단계이고,
+/// 이 셋은 원자적이지 않습니다. 다시 읽은 뒤 쓰기 전에 다른 요청이 쓴 것은 그대로 덮어씁니다.
+/// 게다가 KV는 결과적 일관성이라 다른 위치에서 방금 쓴 값이 한동안 읽히지 않을 수도 있습니다.
+/// 그래서 `check_ply`는 경쟁을 없애지 못하고, 느린 탐색 동안 먼저 둔 수를 덮어쓰는 흔한 경우만 줄입니다.
+/// 동시에 두는 수를 확실히 막아야 하면 Durable Object 같은 원자적인 저장소로 바꿔야 합니다.
+#[derive(Clone)]
+pub struct KvGameStore {
+    kv: SendWrapper<KvStore>,
+}
+
+impl KvGameStore {
+    pub fn new(kv: KvStore) -> KvGameStore {
+        KvGameStore { kv: SendWrapper::new(kv) }
+    }
+
+    fn key(id: &str) -> String {
+        format!("game:{id}")
+    }
+}
+
+impl GameStore for KvGameStore {
+    fn load(&self, id: &str) -> impl Future<Output = Result<Option<Game>, StoreError>> + Send {
+        let kv = self.kv.clone();
+        let key = KvGameStore::key(id);
+        SendFuture::new(async move { kv.get(&key).json().await.map_err(StoreError::backend) })
+    }
+
+    fn save(&self, game: &Game, expected_ply: Option<usize>) -> impl Future<Output = Result<(), StoreError>> + Send {
+        let kv = self.kv.clone();
+        let key = KvGameStore::key(&game.id);
+        let id = game.id.clone();
+        let put = self.kv.put(&key, game).map(|put| put.expiration_ttl(GAME_TTL_SECS));
+        SendFuture::new(async move {
+            let stored: Option<Game> = kv.get(&key).json().await.map_err(StoreError::backend)?;
+            check_ply(&id, stored.as_ref(), expected_ply)?;
+            put.map_err(StoreError::backend)?.execute().await.map_err(StoreError::backend)
+        })
+    }
+}
+
+/// 저장소 바인딩이 없을 때 쓰는 저장소. 게임을 읽고 쓰는 요청을 모두 `StoreError::Backend`로 거절합니다.
+/// isolate의 메모리에 대신 두면 다른 isolate로 간 다음 요청에서 게임이 조용히 사라지기 때문입니다.
+#[derive(Clone, Debug)]
+pub struct MissingGameStore {
+    /// 찾지 못한 바인딩 이름
+    pub binding: &'static str,
+}
+
+impl MissingGameStore {
+    fn error(&self) -> StoreError {
+        StoreError::Backend(format!("게임 저장소 바인딩 {}이(가) 없습니다", self.binding))
+    }
+}
+
+impl GameStore for MissingGameStore {
+    async fn load(&self, _id: &str) -> Result<Option<Game>, StoreError> {
+        Err(self.error())
+    }
+
+    async fn save(&self, _game: &Game, _expected_ply: Option<usize>) -> Result<(), StoreError> {
+        Err(self.error())
+    }
+}
+
+/// 기다리지 않고 끝나는 future를 끝까지 돌립니다. 메모리 저장소만 쓰는 핸들러와 저장소는 기다리는 일이 없습니다.
+#[cfg(test)]
+pub(crate) fn ready<T>(future: impl Future<Output = T>) -> T {
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(value) => value,
+        Poll::Pending => panic!("끝나지 않았습니다"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(id: &str, history: &[&str]) -> Game {
+        let setup: Request = serde_json::from_str(r#"{"script": "", "position": "", "turn": "white"}"#).unwrap();
+        Game {
+            id: id.to_string(),
+            setup: Request {
+                history: history.iter().map(|m| m.to_string()).collect(),
+                ..setup
+            },
+        }
+    }
+
+    #[test]
+    fn memory_store_saves_and_loads() {
+        let store = MemoryGameStore::default();
+        assert!(ready(store.load("a")).unwrap().is_none());
+        ready(store.save(&game("a", &[]), None)).unwrap();
+        ready(store.save(&game("a", &["e2e4"]), Some(0))).unwrap();
+        let loaded = ready(store.load("a")).unwrap().unwrap();
+        assert_eq!(loaded.setup.history, ["e2e4"]);
+        // 복사한 저장소도 같은 게임들을 봅니다.
+        assert!(ready(store.clone().load("a")).unwrap().is_some());
+    }
+
+    #[test]
+    fn stale_saves_are_rejected() {
+        let store = MemoryGameStore::default();
+        ready(store.save(&game("a", &[]), None)).unwrap();
+        // 같은 id로 다시 만들 수 없습니다.
+        assert_eq!(
+            ready(store.save(&game("a", &[]), None)),
+            Err(StoreError::Conflict { id: "a".to_string(), ply: Some(0) })
+        );
+
+        // 둘이 0수에서 불러와 각자 두면 나중에 저장하는 쪽이 거절됩니다.
+        ready(store.save(&game("a", &["e2e4"]), Some(0))).unwrap();
+        let error = ready(store.save(&game("a", &["d2d4"]), Some(0))).unwrap_err();
+        assert_eq!(error, StoreError::Conflict { id: "a".to_string(), ply: Some(1) });
+        assert_eq!(ApiError::from(error).status_code(), 409);
+        assert_eq!(ready(store.load("a")).unwrap().unwrap().setup.history, ["e2e4"]);
+
+        // 없는 게임에 수를 저장할 수도 없습니다.
+        assert_eq!(
+            ready(store.save(&game("b", &["e2e4"]), Some(0))),
+            Err(StoreError::Conflict { id: "b".to_string(), ply: None })
+        );
+    }
+}